@@ -1,24 +1,49 @@
-use crate::button_input::spawn_main_text;
-use crate::deck::spawn_card;
+use crate::button_input::{spawn_button, spawn_main_text};
+use crate::constants::{CARD_H, CARD_W};
+use crate::deck::{spawn_card, BoardCard};
+use crate::localization::Locale;
 use crate::settings::LayoutSettings;
+use crate::sound::{SoundEffect, SoundEvent};
+use crate::states_and_ui::{spawn_dialog, DialogData, DialogItems, MenuData, MenuItems};
+use crate::theme::Theme;
 use crate::{deck::Decks, handle_json::CurrentRunJson, DeckNumber};
 use bevy::prelude::*;
 
 pub fn setup_actual_game(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     current_run_json: Res<CurrentRunJson>,
     mut decks: ResMut<Decks>,
     layout: Res<LayoutSettings>,
+    mut menu_data: ResMut<MenuData>,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
 ) {
-    let entity = spawn_main_text(
-        &mut commands,
-        "",
-        asset_server.load("fonts/Roboto.ttf"),
-        0.0,
-    );
+    let entity = spawn_main_text(&mut commands, "", &theme, 0.0);
 
     commands.entity(entity).insert(ScoreString(()));
+    menu_data.button_entity.push(entity);
+
+    let size = Vec2::new(200.0, 100.0);
+    menu_data.button_entity.push(spawn_button(
+        &mut commands,
+        &theme,
+        &locale.get("game.undo"),
+        theme.button_font_size,
+        1400.0,
+        900.0,
+        size,
+        MenuItems::Undo,
+    ));
+    menu_data.button_entity.push(spawn_button(
+        &mut commands,
+        &theme,
+        &locale.get("game.redo"),
+        theme.button_font_size,
+        1650.0,
+        900.0,
+        size,
+        MenuItems::Redo,
+    ));
 
     let vertical = layout.vertical;
 
@@ -55,7 +80,7 @@ pub fn setup_actual_game(
     let collumns = 2;
 
     for deck in 0..current_run_json.decks.len() {
-        let index_img = 0; // TODO make this a random number
+        let drawn = current_run_json.drawn[deck];
 
         if !vertical && deck != 0 && deck % collumns == 0 {
             mulx = 0.0;
@@ -65,14 +90,15 @@ pub fn setup_actual_game(
         let mut x = startx + (mulx * card_x);
         let mut y = starty + (muly * card_y);
 
-        spawn_card(
+        let entity = spawn_card(
             &mut commands,
             &mut decks,
             deck,
-            index_img,
+            drawn.primary,
             Vec3::new(x, y, 0.0),
             true,
         );
+        menu_data.button_entity.push(entity);
 
         if !vertical {
             x += card_x;
@@ -84,24 +110,118 @@ pub fn setup_actual_game(
             &mut commands,
             &mut decks,
             deck,
-            index_img,
+            drawn.secondary,
             Vec3::new(x, y, 0.0),
             false,
         );
-
-        commands
-            .entity(entity)
-            .insert(Button)
-            .insert(Interaction::None)
-            .insert(DeckNumber { num: deck });
+        menu_data.button_entity.push(entity);
 
         mulx += mulx_adder;
     }
 }
 
-pub fn update_score(score: Res<Score>, mut query: Query<&mut Text, With<ScoreString>>) {
+// keep the drawn cards and completion tint in sync with the run, so undo/redo show up on the board
+pub fn update_board(
+    current_run_json: Res<CurrentRunJson>,
+    decks: Res<Decks>,
+    theme: Res<Theme>,
+    mut query: Query<(&mut TextureAtlasSprite, &DeckNumber, &BoardCard)>,
+) {
+    if !current_run_json.is_changed() {
+        return;
+    }
+
+    for (mut sprite, deck_num, card) in query.iter_mut() {
+        let drawn = current_run_json.drawn[deck_num.num];
+        let together = &decks.0[deck_num.num];
+
+        sprite.index = match card.primary {
+            true => together.primary.offset + drawn.primary,
+            false => together.secondary.offset + drawn.secondary,
+        };
+
+        sprite.color = if current_run_json.completed_decks[deck_num.num] {
+            theme.colors.enabled_deck
+        } else if current_run_json.timers.current == Some(deck_num.num) {
+            theme.colors.in_progress_deck
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+// once every deck is done, ask if the run should be finished
+pub fn check_run_finished(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    current_run_json: Res<CurrentRunJson>,
+    mut dialog_data: ResMut<DialogData>,
+    mut sound_events: EventWriter<SoundEvent>,
+) {
+    if !current_run_json.is_changed() || !current_run_json.is_finished() || dialog_data.is_open() {
+        return;
+    }
+
+    sound_events.send(SoundEvent(SoundEffect::CompleteRun));
+
+    spawn_dialog(
+        &mut commands,
+        &mut dialog_data,
+        &theme,
+        &locale.get("dialog.run_complete.title"),
+        &locale.format("dialog.run_complete.body", &[&current_run_json.score]),
+        &[
+            (DialogItems::FinishRun, locale.get("dialog.finish").as_str()),
+            (
+                DialogItems::ExportAndFinish,
+                locale.get("dialog.export_and_finish").as_str(),
+            ),
+            (
+                DialogItems::Cancel,
+                locale.get("dialog.keep_playing").as_str(),
+            ),
+        ],
+    );
+}
+
+// turns the cursor position into a position on the board
+pub fn cursor_to_world(windows: &Windows, camera: &Transform) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+
+    let size = Vec2::new(window.width(), window.height());
+    let world = camera.compute_matrix() * (cursor - size / 2.0).extend(0.0).extend(1.0);
+
+    Some(world.truncate().truncate())
+}
+
+// returns the deck and whether it was the primary card for the card under the cursor
+pub fn card_under_cursor<'a>(
+    cursor: Vec2,
+    cards: impl Iterator<Item = (&'a Transform, &'a DeckNumber, &'a BoardCard)>,
+) -> Option<(usize, bool)> {
+    for (transform, deck_num, card) in cards {
+        let half_size = Vec2::new(CARD_H, CARD_W) * transform.scale.truncate() / 2.0;
+        let distance = (cursor - transform.translation.truncate()).abs();
+
+        if distance.x <= half_size.x && distance.y <= half_size.y {
+            return Some((deck_num.num, card.primary));
+        }
+    }
+    None
+}
+
+pub fn update_score(
+    mut score: ResMut<Score>,
+    current_run_json: Res<CurrentRunJson>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<ScoreString>>,
+) {
+    score.0 = current_run_json.score as i64;
+
     for mut text in query.iter_mut() {
-        text.sections[0].value = format!("Score: {}", score.0);
+        text.sections[0].value = locale.format("game.score", &[&score.0]);
     }
 }
 
@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+
+use crate::{
+    constants::{CARD_H, CARD_W, SCALE},
+    deck::{BoardCard, DeckBacks},
+    handle_json::{CurrentRunJson, DrawnCards},
+    sound::{SoundEffect, SoundEvent},
+    states_and_ui::{DeckNumber, GameState},
+    MainCamera,
+};
+
+pub struct CardAnimationPlugin;
+
+impl Plugin for CardAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AnimationSettings { enabled: true })
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(deal_cards)
+                    .with_system(animate_run_changes)
+                    .with_system(run_card_animations),
+            );
+    }
+}
+
+const DEAL_TIME: f32 = 0.4;
+const DEAL_GAP: f32 = 0.15; // time between decks being dealt
+const FLIP_TIME: f32 = 0.3;
+const PULSE_TIME: f32 = 0.35;
+const PULSE_SCALE: f32 = 1.15;
+
+// stored in the [Animations] section of settings.ini
+pub struct AnimationSettings {
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+pub struct CardBack {
+    // the deck's back image, shown while a card is face down
+}
+
+#[derive(Component)]
+pub struct CardAnimation {
+    kind: AnimationKind,
+    delay: f32, // seconds to wait before starting
+    elapsed: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum AnimationKind {
+    Deal { from: Vec3, to: Vec3 }, // fly in face down, then flip
+    Flip { revealed: bool },       // turn over to show the face, revealed is set half way
+    Pulse,                         // grow and shrink back, for completing a deck
+}
+
+impl CardAnimation {
+    fn new(kind: AnimationKind, delay: f32) -> Self {
+        CardAnimation {
+            kind,
+            delay,
+            elapsed: 0.0,
+        }
+    }
+}
+
+// slow at the start and end
+fn ease(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// show either the card or its back
+fn set_face_up(
+    face_up: bool,
+    visibility: &mut Visibility,
+    children: &Children,
+    back_query: &mut Query<&mut Visibility, (With<CardBack>, Without<BoardCard>)>,
+) {
+    visibility.is_visible = face_up;
+    for child in children.iter() {
+        if let Ok(mut back) = back_query.get_mut(*child) {
+            back.is_visible = !face_up;
+        }
+    }
+}
+
+// give every new card its back, and deal them onto the board one deck at a time
+fn deal_cards(
+    mut commands: Commands,
+    settings: Res<AnimationSettings>,
+    deck_backs: Res<DeckBacks>,
+    current_run_json: Res<CurrentRunJson>,
+    windows: Res<Windows>,
+    camera_query: Query<&Transform, (With<MainCamera>, Without<BoardCard>)>,
+    mut query: Query<
+        (
+            Entity,
+            &DeckNumber,
+            &BoardCard,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        Added<BoardCard>,
+    >,
+) {
+    // cards come from just under the bottom of the screen
+    let from = match (windows.get_primary(), camera_query.iter().next()) {
+        (Some(window), Some(camera)) => {
+            camera.translation.truncate()
+                - Vec2::new(0.0, (window.height() / 2.0) * camera.scale.y + CARD_W)
+        }
+        _ => Vec2::ZERO,
+    };
+
+    for (entity, deck_num, card, mut transform, mut visibility) in query.iter_mut() {
+        let back = deck_backs.backs[current_run_json.decks[deck_num.num]].clone();
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(CARD_H, CARD_W)),
+                        ..Default::default()
+                    },
+                    texture: back,
+                    transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(CardBack {});
+        });
+
+        if !settings.enabled {
+            continue;
+        }
+
+        let to = transform.translation;
+        let delay =
+            deck_num.num as f32 * DEAL_GAP + if card.primary { 0.0 } else { DEAL_GAP / 2.0 };
+
+        // hidden in the pile until its turn, above the cards that are already down
+        let start = from.extend(to.z + 1.0);
+        transform.translation = start;
+        visibility.is_visible = false;
+        commands.entity(entity).insert(CardAnimation::new(
+            AnimationKind::Deal { from: start, to },
+            delay,
+        ));
+    }
+}
+
+// reroll and completion animations, found by comparing the run to what it was last frame
+fn animate_run_changes(
+    mut commands: Commands,
+    settings: Res<AnimationSettings>,
+    mut sound_events: EventWriter<SoundEvent>,
+    current_run_json: Res<CurrentRunJson>,
+    mut last: Local<Vec<(DrawnCards, bool)>>,
+    new_cards: Query<(), Added<BoardCard>>,
+    mut query: Query<
+        (Entity, &DeckNumber, &BoardCard, &mut Visibility, &Children),
+        Without<CardAnimation>,
+    >,
+    mut back_query: Query<&mut Visibility, (With<CardBack>, Without<BoardCard>)>,
+) {
+    let now: Vec<(DrawnCards, bool)> = current_run_json
+        .drawn
+        .iter()
+        .zip(current_run_json.completed_decks.iter())
+        .map(|(drawn, completed)| (*drawn, *completed))
+        .collect();
+
+    if !new_cards.is_empty() || last.len() != now.len() {
+        // a new board, nothing to compare against yet
+        *last = now;
+        return;
+    }
+    if !current_run_json.is_changed() {
+        *last = now;
+        return;
+    }
+
+    for (entity, deck_num, card, mut visibility, children) in query.iter_mut() {
+        let (before, was_completed) = last[deck_num.num];
+        let (after, completed) = now[deck_num.num];
+
+        let rerolled = match card.primary {
+            true => before.primary != after.primary,
+            false => before.secondary != after.secondary,
+        };
+
+        // the sounds play even with animations off, the flip sound comes from the animation otherwise
+        if rerolled && !settings.enabled {
+            sound_events.send(SoundEvent(SoundEffect::Flip));
+        } else if completed && !was_completed && card.primary {
+            sound_events.send(SoundEvent(SoundEffect::CompleteDeck));
+        }
+        if !settings.enabled {
+            continue;
+        }
+
+        if rerolled {
+            // turn the old card over and show the new one
+            set_face_up(false, &mut visibility, children, &mut back_query);
+            commands.entity(entity).insert(CardAnimation::new(
+                AnimationKind::Flip { revealed: false },
+                0.0,
+            ));
+        } else if completed && !was_completed {
+            commands
+                .entity(entity)
+                .insert(CardAnimation::new(AnimationKind::Pulse, 0.0));
+        }
+    }
+    *last = now;
+}
+
+fn run_card_animations(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sound_events: EventWriter<SoundEvent>,
+    mut query: Query<
+        (
+            Entity,
+            &mut CardAnimation,
+            &mut Transform,
+            &mut Visibility,
+            &Children,
+        ),
+        With<BoardCard>,
+    >,
+    mut back_query: Query<&mut Visibility, (With<CardBack>, Without<BoardCard>)>,
+) {
+    for (entity, mut animation, mut transform, mut visibility, children) in query.iter_mut() {
+        if animation.delay > 0.0 {
+            animation.delay -= time.delta_seconds();
+            continue;
+        }
+        animation.elapsed += time.delta_seconds();
+
+        match animation.kind {
+            AnimationKind::Deal { from, to } => {
+                set_face_up(false, &mut visibility, children, &mut back_query);
+
+                let t = ease(animation.elapsed / DEAL_TIME);
+                transform.translation = from.lerp(to, t);
+
+                if animation.elapsed >= DEAL_TIME {
+                    transform.translation = to;
+                    *animation = CardAnimation::new(AnimationKind::Flip { revealed: false }, 0.0);
+                }
+            }
+            AnimationKind::Flip { revealed } => {
+                // squash to nothing, swap to the face, then stretch back out
+                let t = (animation.elapsed / FLIP_TIME).min(1.0);
+                transform.scale.x = SCALE * (1.0 - 2.0 * t).abs();
+
+                if t >= 0.5 && !revealed {
+                    set_face_up(true, &mut visibility, children, &mut back_query);
+                    animation.kind = AnimationKind::Flip { revealed: true };
+                    sound_events.send(SoundEvent(SoundEffect::Flip));
+                }
+                if t >= 1.0 {
+                    transform.scale.x = SCALE;
+                    commands.entity(entity).remove::<CardAnimation>();
+                }
+            }
+            AnimationKind::Pulse => {
+                let t = (animation.elapsed / PULSE_TIME).min(1.0);
+                let grow = 1.0 + (PULSE_SCALE - 1.0) * (t * std::f32::consts::PI).sin();
+                transform.scale = Vec3::new(SCALE * grow, SCALE * grow, SCALE);
+
+                if t >= 1.0 {
+                    transform.scale = Vec3::new(SCALE, SCALE, SCALE);
+                    commands.entity(entity).remove::<CardAnimation>();
+                }
+            }
+        }
+    }
+}
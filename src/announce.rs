@@ -0,0 +1,246 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    process::{Child, Command},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    export::CardSummary, handle_json::DeckDataWrapper, localization::Locale, run_events::RunEvent,
+    settings::Settings, states_and_ui::MenuItems,
+};
+
+pub struct AnnouncePlugin;
+
+impl Plugin for AnnouncePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Announcement>()
+            .insert_resource(AnnounceSettings::default())
+            .insert_resource(Speaker { child: None })
+            .add_system(announce_focus)
+            .add_system(announce_run_events.after("run_events"))
+            .add_system(speak);
+    }
+}
+
+// send one of these to have something read out
+pub struct Announcement(pub String);
+
+// stored in the [Accessibility] section of settings.ini
+#[derive(Debug, Clone, Default)]
+pub struct AnnounceSettings {
+    pub enabled: bool,
+    pub command: String, // text to speech program, the text is added as the last argument. empty to not speak
+    pub log: String, // file every announcement gets added to, for screen readers that watch a file
+}
+
+impl AnnounceSettings {
+    pub fn load(&mut self, settings: &Settings) {
+        if let Ok(Some(enabled)) = settings.settings.getbool("Accessibility", "announcements") {
+            self.enabled = enabled;
+        }
+        self.command = settings
+            .settings
+            .get("Accessibility", "tts_command")
+            .unwrap_or_default();
+        self.log = settings
+            .settings
+            .get("Accessibility", "announcement_log")
+            .unwrap_or_default();
+    }
+
+    pub fn save(&self, settings: &mut Settings) {
+        settings.settings.set(
+            "Accessibility",
+            "announcements",
+            Some(self.enabled.to_string()),
+        );
+        settings.update();
+    }
+}
+
+// the text to speech program that is talking right now
+struct Speaker {
+    child: Option<Child>,
+}
+
+// read out whatever the mouse moves onto, with the state of the deck for deck backs
+fn announce_focus(
+    locale: Res<Locale>,
+    mut focused: Local<Option<Entity>>,
+    mut announcements: EventWriter<Announcement>,
+    query: Query<
+        (Entity, &Interaction, Option<&MenuItems>, Option<&Children>),
+        (With<Button>, Changed<Interaction>),
+    >,
+    text_query: Query<(&Text, &Visibility)>,
+) {
+    for (entity, interaction, menu_item, children) in query.iter() {
+        match interaction {
+            // clicking a button makes it hovered again, that shouldnt read it out twice
+            Interaction::Hovered | Interaction::Clicked if *focused == Some(entity) => continue,
+            Interaction::Hovered | Interaction::Clicked => *focused = Some(entity),
+            Interaction::None => {
+                if *focused == Some(entity) {
+                    *focused = None;
+                }
+                continue;
+            }
+        }
+
+        // buttons that are just a picture
+        let name = match menu_item {
+            Some(MenuItems::Back) => locale.get("announce.back"),
+            Some(MenuItems::Settings) => locale.get("announce.settings"),
+            Some(MenuItems::Play) => locale.get("announce.play"),
+            _ => String::new(),
+        };
+
+        let mut label: Vec<&str> = children
+            .iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|child| text_query.get(*child).ok())
+            .filter(|(_, visibility)| visibility.is_visible)
+            .flat_map(|(text, _)| text.sections.iter().map(|x| x.value.trim()))
+            .filter(|x| !x.is_empty())
+            .collect();
+        if label.is_empty() && !name.is_empty() {
+            label.push(&name);
+        }
+
+        if !label.is_empty() {
+            announcements.send(Announcement(label.join(", ")));
+        }
+    }
+}
+
+fn describe_draw(
+    deck_data: &DeckDataWrapper,
+    locale: &Locale,
+    deck: usize,
+    primary: &CardSummary,
+    secondary: &CardSummary,
+) -> String {
+    let mut text = locale.format(
+        "announce.drawn",
+        &[
+            &deck_data.decks[deck].display_name(&locale.language),
+            &(primary.index + 1),
+            &(secondary.index + 1),
+        ],
+    );
+    for card_text in [&primary.text, &secondary.text].into_iter().flatten() {
+        text += &format!(". {}", card_text);
+    }
+    text
+}
+
+// picked decks in the pre-game, the whole board when it is dealt, and drawn cards, completions and the score in game
+fn announce_run_events(
+    deck_data: Res<DeckDataWrapper>,
+    locale: Res<Locale>,
+    mut run_events: EventReader<RunEvent>,
+    mut announcements: EventWriter<Announcement>,
+) {
+    let name = |deck: usize| {
+        deck_data.decks[deck]
+            .display_name(&locale.language)
+            .to_owned()
+    };
+
+    for event in run_events.iter() {
+        let text = match event {
+            RunEvent::DeckSelected { deck, selected, .. } => {
+                let key = match selected {
+                    true => "announce.selected",
+                    false => "announce.removed",
+                };
+                locale.format(key, &[&name(*deck)])
+            }
+            RunEvent::CardDrawn {
+                deck,
+                primary,
+                secondary,
+                ..
+            } => describe_draw(&deck_data, &locale, *deck, primary, secondary),
+            RunEvent::DeckCompleted {
+                deck, completed, ..
+            } => {
+                let key = match completed {
+                    true => "announce.completed",
+                    false => "announce.uncompleted",
+                };
+                locale.format(key, &[&name(*deck)])
+            }
+            RunEvent::ScoreChanged { score, .. } => locale.format("game.score", &[score]),
+            RunEvent::RunFinished { score, .. } => {
+                locale.format("dialog.run_complete.body", &[score])
+            }
+            RunEvent::RunStarted { decks, .. } => {
+                let mut text = locale.format("announce.run_started", &[&decks.len()]);
+                for dealt in decks.iter() {
+                    text += ". ";
+                    text += &describe_draw(
+                        &deck_data,
+                        &locale,
+                        dealt.deck,
+                        &dealt.primary,
+                        &dealt.secondary,
+                    );
+                }
+                text
+            }
+        };
+        announcements.send(Announcement(text));
+    }
+}
+
+// write everything to the log, and say the newest things, cutting off whatever was being said
+fn speak(
+    settings: Res<AnnounceSettings>,
+    mut speaker: ResMut<Speaker>,
+    mut announcements: EventReader<Announcement>,
+) {
+    let lines: Vec<&str> = announcements.iter().map(|x| x.0.as_str()).collect();
+    if !settings.enabled || lines.is_empty() {
+        return;
+    }
+
+    if !settings.log.is_empty() {
+        match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&settings.log)
+        {
+            Ok(mut file) => {
+                for line in lines.iter() {
+                    let _ = writeln!(file, "{}", line);
+                }
+            }
+            Err(e) => println!("Could not write to {}: {}", settings.log, e),
+        }
+    }
+
+    let mut args = settings.command.split_whitespace();
+    let program = match args.next() {
+        Some(program) => program,
+        None => return,
+    };
+
+    if let Some(mut child) = speaker.child.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    speaker.child = match Command::new(program)
+        .args(args)
+        .arg(lines.join(". "))
+        .spawn()
+    {
+        Ok(child) => Some(child),
+        Err(e) => {
+            println!("Could not run {}: {}", program, e);
+            None
+        }
+    };
+}
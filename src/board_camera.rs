@@ -0,0 +1,299 @@
+use bevy::{input::mouse::MouseWheel, prelude::*};
+
+use crate::{
+    button_input::spawn_button,
+    constants::{CARD_H, CARD_W},
+    deck::BoardCard,
+    handle_json::CurrentRunJson,
+    localization::Locale,
+    states_and_ui::{DeckNumber, DialogData, GameState, MenuData, MenuItems},
+    theme::Theme,
+    MainCamera,
+};
+
+pub struct BoardCameraPlugin;
+
+impl Plugin for BoardCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BoardView::default())
+            .insert_resource(BoardDrag::default())
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_board_camera))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(pan_and_zoom_board.label("board_drag"))
+                    .with_system(handle_camera_buttons),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(reset_camera));
+    }
+}
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 6.0;
+const DRAG_THRESHOLD: f32 = 6.0; // pixels the mouse has to move before a click turns into a drag
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraItems {
+    FitAll,
+    FocusCurrent,
+}
+
+// where the board camera is looking, kept around so leaving and coming back doesnt lose your place
+#[derive(Debug, Clone, Copy)]
+pub struct BoardView {
+    pub translation: Vec2,
+    pub zoom: f32, // 1.0 is the normal size, bigger is further out
+}
+
+impl Default for BoardView {
+    fn default() -> Self {
+        BoardView {
+            translation: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+// left click and drag pans the board, a click only counts if the mouse didnt move
+#[derive(Default, Debug)]
+pub struct BoardDrag {
+    last: Option<Vec2>, // cursor position last frame while the button is held
+    moved: f32,
+    pub dragged: bool, // stays set until the next press so the release can check it
+    pub on_board: bool, // the press wasnt on a button or while a dialog was open
+}
+
+// the area every card on the board covers
+fn board_bounds<'a>(cards: impl Iterator<Item = &'a Transform>) -> Option<Rect<f32>> {
+    let mut bounds: Option<Rect<f32>> = None;
+
+    for transform in cards {
+        let half = Vec2::new(CARD_H, CARD_W) * transform.scale.truncate() / 2.0;
+        let min = transform.translation.truncate() - half;
+        let max = transform.translation.truncate() + half;
+
+        bounds = Some(match bounds {
+            Some(bounds) => Rect {
+                left: bounds.left.min(min.x),
+                right: bounds.right.max(max.x),
+                bottom: bounds.bottom.min(min.y),
+                top: bounds.top.max(max.y),
+            },
+            None => Rect {
+                left: min.x,
+                right: max.x,
+                bottom: min.y,
+                top: max.y,
+            },
+        });
+    }
+    bounds
+}
+
+impl BoardView {
+    // keep the middle of the screen on the board
+    fn clamp(&mut self, bounds: Option<Rect<f32>>) {
+        self.zoom = self.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+
+        if let Some(bounds) = bounds {
+            self.translation.x = self.translation.x.clamp(bounds.left, bounds.right);
+            self.translation.y = self.translation.y.clamp(bounds.bottom, bounds.top);
+        }
+    }
+
+    // zoom out until the whole area fits on screen
+    fn fit(&mut self, area: Rect<f32>, window: &Window) {
+        let size = Vec2::new(area.right - area.left, area.top - area.bottom) * 1.1;
+
+        self.translation = Vec2::new(area.left + area.right, area.bottom + area.top) / 2.0;
+        self.zoom = (size.x / window.width()).max(size.y / window.height());
+    }
+
+    fn apply(&self, transform: &mut Transform) {
+        transform.translation.x = self.translation.x;
+        transform.translation.y = self.translation.y;
+        transform.scale = Vec3::new(self.zoom, self.zoom, 1.0);
+    }
+}
+
+fn setup_board_camera(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    mut menu_data: ResMut<MenuData>,
+    view: Res<BoardView>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    for mut transform in camera_query.iter_mut() {
+        view.apply(&mut transform);
+    }
+
+    let buttons = [
+        (CameraItems::FitAll, "camera.fit_all", 1400.0),
+        (CameraItems::FocusCurrent, "camera.focus", 1650.0),
+    ];
+    for (item, key, x) in buttons {
+        menu_data.button_entity.push(spawn_button(
+            &mut commands,
+            &theme,
+            &locale.get(key),
+            theme.button_font_size,
+            x,
+            660.0,
+            Vec2::new(200.0, 100.0),
+            MenuItems::Camera(item),
+        ));
+    }
+}
+
+// the other states dont use the 2d camera, put it back where it started
+fn reset_camera(mut camera_query: Query<&mut Transform, With<MainCamera>>) {
+    for mut transform in camera_query.iter_mut() {
+        BoardView::default().apply(&mut transform);
+    }
+}
+
+fn pan_and_zoom_board(
+    mut view: ResMut<BoardView>,
+    mut drag: ResMut<BoardDrag>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mouse: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    windows: Res<Windows>,
+    dialog_data: Res<DialogData>,
+    ui_query: Query<&Interaction, With<Button>>,
+    card_query: Query<&Transform, (With<BoardCard>, Without<MainCamera>)>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let cursor = window.cursor_position();
+    let over_ui = ui_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+
+    let mut new_view = *view;
+
+    if mouse.just_pressed(MouseButton::Left) {
+        // buttons and dialogs get the click, not the board
+        *drag = BoardDrag::default();
+        if !over_ui && !dialog_data.is_open() {
+            drag.last = cursor;
+            drag.on_board = true;
+        }
+    }
+    if mouse.pressed(MouseButton::Left) {
+        if let (Some(last), Some(cursor)) = (drag.last, cursor) {
+            let delta = cursor - last;
+            drag.moved += delta.length();
+            drag.dragged |= drag.moved > DRAG_THRESHOLD;
+
+            if drag.dragged {
+                // move the board with the cursor
+                new_view.translation -= delta * new_view.zoom;
+            }
+            drag.last = Some(cursor);
+        }
+    } else {
+        drag.last = None;
+    }
+
+    let mut zoom = 1.0;
+    if !dialog_data.is_open() {
+        for event in mouse_wheel_events.iter() {
+            // scrolling up zooms in
+            zoom *= 1.1_f32.powf(-event.y.clamp(-3.0, 3.0));
+        }
+    }
+
+    let pinch: Vec<_> = touches.iter().take(2).collect();
+    if let [first, second] = pinch[..] {
+        let before = first
+            .previous_position()
+            .distance(second.previous_position());
+        let after = first.position().distance(second.position());
+        if before > 0.0 && after > 0.0 {
+            // fingers moving apart zooms in
+            zoom *= before / after;
+        }
+    }
+
+    if zoom != 1.0 {
+        let old_zoom = new_view.zoom;
+        new_view.zoom = (old_zoom * zoom).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        // keep the point under the cursor in the same place
+        if let Some(cursor) = cursor {
+            let from_center = cursor - Vec2::new(window.width(), window.height()) / 2.0;
+            new_view.translation += from_center * (old_zoom - new_view.zoom);
+        }
+    }
+
+    new_view.clamp(board_bounds(card_query.iter()));
+
+    if new_view.translation != view.translation || new_view.zoom != view.zoom {
+        *view = new_view;
+        for mut transform in camera_query.iter_mut() {
+            view.apply(&mut transform);
+        }
+    }
+}
+
+fn handle_camera_buttons(
+    mut view: ResMut<BoardView>,
+    current_run_json: Res<CurrentRunJson>,
+    windows: Res<Windows>,
+    dialog_data: Res<DialogData>,
+    card_query: Query<(&Transform, &DeckNumber), (With<BoardCard>, Without<MainCamera>)>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    query: Query<(&Interaction, &MenuItems), (With<Button>, Changed<Interaction>)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    if dialog_data.is_open() {
+        return;
+    }
+
+    for (interaction, menu_item) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        let area = match menu_item {
+            MenuItems::Camera(CameraItems::FitAll) => {
+                board_bounds(card_query.iter().map(|(transform, _)| transform))
+            }
+            MenuItems::Camera(CameraItems::FocusCurrent) => {
+                // the deck in progress, otherwise the first one that still needs doing
+                let deck = current_run_json.timers.current.or_else(|| {
+                    current_run_json
+                        .completed_decks
+                        .iter()
+                        .position(|completed| !completed)
+                });
+                deck.and_then(|deck| {
+                    board_bounds(
+                        card_query
+                            .iter()
+                            .filter(|(_, deck_num)| deck_num.num == deck)
+                            .map(|(transform, _)| transform),
+                    )
+                })
+            }
+            _ => continue,
+        };
+
+        if let Some(area) = area {
+            view.fit(area, window);
+            view.clamp(board_bounds(
+                card_query.iter().map(|(transform, _)| transform),
+            ));
+            for mut transform in camera_query.iter_mut() {
+                view.apply(&mut transform);
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use crate::{
+    button_input::spawn_button,
+    deck_filter::DeckFilter,
+    handle_json::{DeckDataWrapper, EnabledJson, RunHistoryJson},
+    localization::Locale,
+    states_and_ui::{DialogData, GameState, MenuData, MenuItems},
+    theme::Theme,
+};
+
+pub struct BulkSelectPlugin;
+
+impl Plugin for BulkSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::DeckSelection).with_system(setup_bulk_select),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::DeckSelection)
+                .with_system(handle_bulk_buttons)
+                .with_system(update_save_label),
+        );
+    }
+}
+
+// these only change the decks that pass the current filter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BulkItems {
+    EnableAll,
+    DisableAll,
+    Invert,
+}
+
+fn setup_bulk_select(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    mut menu_data: ResMut<MenuData>,
+) {
+    let size = Vec2::new(250.0, 100.0);
+
+    // in a row next to the save button
+    let buttons = [
+        (BulkItems::EnableAll, "bulk.enable_all", 260.0),
+        (BulkItems::DisableAll, "bulk.disable_all", 520.0),
+        (BulkItems::Invert, "bulk.invert", 780.0),
+    ];
+    for (item, key, x) in buttons {
+        menu_data.button_entity.push(spawn_button(
+            &mut commands,
+            &theme,
+            &locale.get(key),
+            theme.button_font_size,
+            x,
+            0.0,
+            size,
+            MenuItems::Bulk(item),
+        ));
+    }
+}
+
+fn handle_bulk_buttons(
+    mut enabled_json: ResMut<EnabledJson>,
+    filter: Res<DeckFilter>,
+    deck_data: Res<DeckDataWrapper>,
+    run_history: Res<RunHistoryJson>,
+    locale: Res<Locale>,
+    dialog_data: Res<DialogData>,
+    query: Query<(&Interaction, &MenuItems), (With<Button>, Changed<Interaction>)>,
+) {
+    if dialog_data.is_open() {
+        return;
+    }
+
+    for (interaction, menu_item) in query.iter() {
+        let item = match (interaction, menu_item) {
+            (Interaction::Clicked, MenuItems::Bulk(item)) => *item,
+            _ => continue,
+        };
+
+        let visible = filter.visible_decks(&deck_data, &enabled_json, &run_history, &locale);
+        for deck in visible {
+            let enable = match item {
+                BulkItems::EnableAll => true,
+                BulkItems::DisableAll => false,
+                BulkItems::Invert => !enabled_json.check_enabled(&deck),
+            };
+
+            if enable && !enabled_json.check_enabled(&deck) {
+                enabled_json.enable(deck);
+            } else if !enable && !enabled_json.check_disabled(&deck) {
+                enabled_json.disable(deck);
+            }
+        }
+    }
+}
+
+// put a * on the save button while there are unsaved changes
+fn update_save_label(
+    enabled_json: Res<EnabledJson>,
+    locale: Res<Locale>,
+    save_query: Query<&MenuItems, Changed<Interaction>>,
+    query: Query<(&MenuItems, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    // the label only changes when decks are toggled or saved
+    let saved = save_query
+        .iter()
+        .any(|menu_item| matches!(menu_item, MenuItems::Save | MenuItems::Preset(_)));
+    if !enabled_json.is_changed() && !saved {
+        return;
+    }
+
+    let label = match enabled_json.is_saved() {
+        true => locale.get("button.save"),
+        false => locale.get("button.save_unsaved"),
+    };
+
+    for (menu_item, children) in query.iter() {
+        if !matches!(menu_item, MenuItems::Save) {
+            continue;
+        }
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
@@ -1,11 +1,20 @@
-use bevy::{input::mouse::MouseWheel, prelude::*};
+use bevy::prelude::*;
 use std::path::Path;
 
 use crate::{
+    actual_game::{card_under_cursor, cursor_to_world},
+    board_camera::BoardDrag,
     constants::*,
+    deck::BoardCard,
+    deck_filter::DeckFilter,
     handle_json::*,
-    settings::{Colors, LayoutSettings},
+    inspect::Inspected,
+    localization::Locale,
+    run_actions::RunAction,
     states_and_ui::*,
+    theme::Theme,
+    timer::RunClock,
+    MainCamera,
 };
 
 pub struct LastMenu {
@@ -17,61 +26,96 @@ impl Plugin for ButtonInputPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(LastMenu {
             last: GameState::MainMenu,
-        });
+        })
+        .add_system(handle_text_input);
     }
 }
 
+// a box that can be clicked on and typed into
+#[derive(Component, Default)]
+pub struct TextInput {
+    pub value: String,
+    pub placeholder: String, // shown while the box is empty
+    pub focused: bool,
+}
+
 pub fn handle_choosing_cards(
     state: ResMut<State<GameState>>,
+    dialog_data: Res<DialogData>,
+    globals: Res<GameGlobals>,
+    keys: Res<Input<KeyCode>>,
+    filter: Res<DeckFilter>,
+    deck_data: Res<DeckDataWrapper>,
+    run_history: Res<RunHistoryJson>,
+    locale: Res<Locale>,
+    mut anchor: Local<Option<usize>>, // the last deck clicked in deck selection, where shift-click ranges start
     mut enabled_json: ResMut<EnabledJson>,
     mut current_run_json: ResMut<CurrentRunJson>,
-    mut interaction_query: Query<
-        (&Interaction, &DeckNumber, &mut UiColor),
-        (Changed<Interaction>, With<Button>),
-    >,
+    interaction_query: Query<(&Interaction, &DeckNumber), (Changed<Interaction>, With<Button>)>,
 ) {
-    if enabled_json.is_changed() {
+    if enabled_json.is_changed() || dialog_data.is_open() {
         return;
     }
 
-    for (interaction, deck_num, mut color) in interaction_query.iter_mut() {
+    for (interaction, deck_num) in interaction_query.iter() {
         if interaction == &Interaction::Clicked {
             //screen_print!("Deck Selected: {}", deck_num.num);
 
             if *state.current() == GameState::PreGame {
-                if current_run_json.check_deck(&deck_num.num) {
-                    // if its enabled, disable it
+                if !current_run_json.check_deck(&deck_num.num)
+                    && current_run_json.decks.len() >= globals.decks_per_game
+                {
+                    // the run is already full
+                    continue;
+                }
 
-                    current_run_json.disable_deck(deck_num.num);
+                // if its enabled, disable it, otherwise enable it. the color gets updated in update_pre_game_colors
+                let command = current_run_json.toggle_deck_command(deck_num.num);
+                current_run_json.apply(command);
+            } else {
+                // the color gets updated in update_deck_selection_colors
+                let enable = !enabled_json.check_enabled(&deck_num.num);
 
-                    *color = Default::default();
-                } else {
-                    // if its disabled, enable it
-                    current_run_json.enable_deck(deck_num.num);
-                    *color = ENABLED_DECK.into();
+                let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+                let mut range = vec![deck_num.num];
+                if let (true, Some(start)) = (shift, *anchor) {
+                    // everything between the last clicked deck and this one, in the order they are shown
+                    let visible =
+                        filter.visible_decks(&deck_data, &enabled_json, &run_history, &locale);
+                    let start = visible.iter().position(|x| *x == start);
+                    let end = visible.iter().position(|x| *x == deck_num.num);
+                    if let (Some(start), Some(end)) = (start, end) {
+                        range = visible[start.min(end)..=start.max(end)].to_vec();
+                    }
                 }
-            } else {
-                if enabled_json.check_enabled(&deck_num.num) {
-                    // if its enabled, disable it
-                    enabled_json.disable(deck_num.num);
-                    *color = DISABLED_DECK.into();
-                } else {
-                    // if its disabled, enable it
-                    enabled_json.enable(deck_num.num);
-
-                    *color = Default::default();
+
+                for deck in range {
+                    if enable && !enabled_json.check_enabled(&deck) {
+                        enabled_json.enable(deck);
+                    } else if !enable && !enabled_json.check_disabled(&deck) {
+                        enabled_json.disable(deck);
+                    }
                 }
+                *anchor = Some(deck_num.num);
             }
         }
     }
 }
 
 pub fn handle_ui_buttons(
+    mut commands: Commands,
     mut state: ResMut<State<GameState>>,
     mut last_menu: ResMut<LastMenu>,
-    enabled_json: Res<EnabledJson>,
-    colors: Res<Colors>,
+    mut dialog_data: ResMut<DialogData>,
+    mut enabled_json: ResMut<EnabledJson>,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
     mut current_run_json: ResMut<CurrentRunJson>,
+    mut run_history: ResMut<RunHistoryJson>,
+    deck_data: Res<DeckDataWrapper>,
+    globals: Res<GameGlobals>,
+    mut clock: ResMut<RunClock>,
+    mut run_actions: EventWriter<RunAction>,
     mut interaction_query: Query<
         (&Interaction, &mut UiColor, &MenuItems),
         (Changed<Interaction>, With<Button>),
@@ -80,40 +124,121 @@ pub fn handle_ui_buttons(
     for (interaction, mut color, menu_items) in interaction_query.iter_mut() {
         match *interaction {
             Interaction::Clicked => {
-                *color = PRESSED_BUTTON.into();
+                if dialog_data.is_open() && !matches!(menu_items, MenuItems::Dialog(_)) {
+                    // only the dialog can be interacted with while its open
+                    continue;
+                }
+
+                *color = theme.colors.pressed_button.into();
 
                 match menu_items {
                     MenuItems::HowToPlay => state.set(GameState::HowTo).unwrap(),
                     MenuItems::Continue => {
                         if !Path::new("config/current_run.json").exists() {
                             // check if save data exists
-                            *color = colors.disabled_button.into();
+                            *color = theme.colors.disabled_button.into();
+                        } else if let Err(e) = current_run_json.load() {
+                            println!("Could not load the saved run: {}", e);
+                            // the save cant be continued, offer to start over it instead
+                            spawn_dialog(
+                                &mut commands,
+                                &mut dialog_data,
+                                &theme,
+                                &locale.get("dialog.broken_save.title"),
+                                &locale.get("dialog.broken_save.body"),
+                                &[
+                                    (
+                                        DialogItems::Overwrite,
+                                        locale.get("dialog.overwrite").as_str(),
+                                    ),
+                                    (DialogItems::Cancel, locale.get("dialog.cancel").as_str()),
+                                ],
+                            );
                         } else {
-                            current_run_json.load();
                             state.set(GameState::PreGame).unwrap();
                         }
                     }
                     MenuItems::NewGame => {
-                        // delete the save file if it exists, then enter pre-game
-                        let path = Path::new("config/current_run.json");
-                        if path.exists() {
-                            std::fs::remove_file(path).unwrap();
+                        if Path::new("config/current_run.json").exists() {
+                            // dont throw away a run without asking first
+                            spawn_dialog(
+                                &mut commands,
+                                &mut dialog_data,
+                                &theme,
+                                &locale.get("dialog.overwrite.title"),
+                                &locale.get("dialog.overwrite.body"),
+                                &[
+                                    (
+                                        DialogItems::Overwrite,
+                                        locale.get("dialog.overwrite").as_str(),
+                                    ),
+                                    (DialogItems::Cancel, locale.get("dialog.cancel").as_str()),
+                                ],
+                            );
+                        } else {
+                            *current_run_json = CurrentRunJson::default();
+                            state.set(GameState::PreGame).unwrap();
                         }
-                        state.set(GameState::PreGame).unwrap();
                     }
                     MenuItems::DeckSelection => {
                         last_menu.last = GameState::MainMenu;
                         state.set(GameState::DeckSelection).unwrap()
                     }
+                    MenuItems::Stats => {
+                        last_menu.last = GameState::MainMenu;
+                        state.set(GameState::Stats).unwrap()
+                    }
                     MenuItems::Quit => state.set(GameState::Quit).unwrap(),
                     MenuItems::Save => enabled_json.update(), // store struct in file
-                    MenuItems::Play => state.set(GameState::InGame).unwrap(),
+                    MenuItems::Play => {
+                        // the button is hidden until the run is full, but check anyway
+                        if current_run_json.decks.len() == globals.decks_per_game {
+                            state.set(GameState::InGame).unwrap();
+                        }
+                    }
+                    MenuItems::Undo => run_actions.send(RunAction::Undo),
+                    MenuItems::Redo => run_actions.send(RunAction::Redo),
                     MenuItems::Back => {
                         match *state.current() {
                             GameState::InGame => {
-                                // TODO make dialogue that asks you to save or quit
-                                current_run_json.update();
-                                state.set(GameState::MainMenu).unwrap();
+                                spawn_dialog(
+                                    &mut commands,
+                                    &mut dialog_data,
+                                    &theme,
+                                    &locale.get("dialog.leave.title"),
+                                    &locale.get("dialog.leave.body"),
+                                    &[
+                                        (
+                                            DialogItems::SaveAndQuit,
+                                            locale.get("dialog.save_and_quit").as_str(),
+                                        ),
+                                        (
+                                            DialogItems::QuitWithoutSaving,
+                                            locale.get("dialog.quit").as_str(),
+                                        ),
+                                        (DialogItems::Cancel, locale.get("dialog.cancel").as_str()),
+                                    ],
+                                );
+                            }
+                            GameState::DeckSelection if !enabled_json.is_saved() => {
+                                spawn_dialog(
+                                    &mut commands,
+                                    &mut dialog_data,
+                                    &theme,
+                                    &locale.get("dialog.unsaved.title"),
+                                    &locale.get("dialog.unsaved.body"),
+                                    &[
+                                        (
+                                            DialogItems::SaveDecks,
+                                            locale.get("dialog.save").as_str(),
+                                        ),
+                                        (
+                                            DialogItems::DiscardDecks,
+                                            locale.get("dialog.discard").as_str(),
+                                        ),
+                                        (DialogItems::Cancel, locale.get("dialog.cancel").as_str()),
+                                    ],
+                                );
                             }
                             _ => {
                                 if *state.current() != last_menu.last {
@@ -123,16 +248,64 @@ pub fn handle_ui_buttons(
                             }
                         }
                     }
+                    MenuItems::Dialog(dialog_item) => {
+                        close_dialog(&mut commands, &mut dialog_data);
+
+                        match dialog_item {
+                            DialogItems::SaveAndQuit => {
+                                clock.write(&mut current_run_json);
+                                current_run_json.update();
+                                state.set(GameState::MainMenu).unwrap();
+                            }
+                            DialogItems::QuitWithoutSaving => {
+                                state.set(GameState::MainMenu).unwrap();
+                            }
+                            DialogItems::Overwrite => {
+                                // remember the abandoned run, delete the save file, then enter pre-game
+                                let path = Path::new("config/current_run.json");
+                                match CurrentRunJson::read_saved() {
+                                    Some(old_run) => run_history.archive(&old_run, &deck_data),
+                                    // a broken save still gets thrown away, it just isnt remembered
+                                    None => println!("Could not read the old run"),
+                                }
+
+                                if path.exists() {
+                                    std::fs::remove_file(path).unwrap();
+                                }
+                                *current_run_json = CurrentRunJson::default();
+                                state.set(GameState::PreGame).unwrap();
+                            }
+                            DialogItems::FinishRun | DialogItems::ExportAndFinish => {
+                                run_history.archive(&current_run_json, &deck_data);
+
+                                let path = Path::new("config/current_run.json");
+                                if path.exists() {
+                                    std::fs::remove_file(path).unwrap();
+                                }
+                                *current_run_json = CurrentRunJson::default();
+                                state.set(GameState::MainMenu).unwrap();
+                            }
+                            DialogItems::SaveDecks => {
+                                enabled_json.update();
+                                state.set(last_menu.last).unwrap();
+                            }
+                            DialogItems::DiscardDecks => {
+                                enabled_json.load(); // go back to what was saved
+                                state.set(last_menu.last).unwrap();
+                            }
+                            DialogItems::Cancel => {}
+                        }
+                    }
                     MenuItems::Settings => state.set(GameState::Settings).unwrap(),
                     _ => {}
                 }
             }
 
             Interaction::Hovered => {
-                *color = colors.hovered_button.into();
+                *color = theme.colors.hovered_button.into();
             }
             Interaction::None => {
-                *color = colors.normal_button.into();
+                *color = theme.colors.normal_button.into();
             }
         }
     }
@@ -140,7 +313,7 @@ pub fn handle_ui_buttons(
 
 pub fn spawn_button(
     commands: &mut Commands,
-    font: Handle<Font>,
+    theme: &Theme,
     text: &str,
     font_size: f32,
     posx: f32,
@@ -162,6 +335,7 @@ pub fn spawn_button(
                 align_items: AlignItems::Center,
                 ..Default::default()
             },
+            color: theme.colors.normal_button.into(),
             ..Default::default()
         })
         .with_children(|parent| {
@@ -169,9 +343,9 @@ pub fn spawn_button(
                 text: Text::with_section(
                     text,
                     TextStyle {
-                        font,
+                        font: theme.font.clone(),
                         font_size,
-                        color: Color::rgb(0.9, 0.9, 0.9),
+                        color: theme.text_color,
                     },
                     Default::default(),
                 ),
@@ -211,12 +385,148 @@ pub fn spawn_button_img(
         .id()
 }
 
-pub fn spawn_main_text(
+pub fn spawn_text_input(
+    commands: &mut Commands,
+    theme: &Theme,
+    placeholder: &str,
+    font_size: f32,
+    posx: f32,
+    posy: f32,
+    size: Vec2,
+) -> Entity {
+    commands
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(size[0]), Val::Px(size[1])),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(posy),
+                    left: Val::Px(posx),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: theme.colors.normal_button.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    placeholder,
+                    TextStyle {
+                        font: theme.font.clone(),
+                        font_size,
+                        color: theme.text_color,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        })
+        .insert(TextInput {
+            placeholder: placeholder.to_owned(),
+            ..Default::default()
+        })
+        .id()
+}
+
+// focus text inputs when they get clicked and type into the focused one
+pub fn handle_text_input(
+    mut chars: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    theme: Res<Theme>,
+    mut query: Query<(&Interaction, &mut TextInput, &mut UiColor, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    let typed: Vec<char> = chars.iter().map(|event| event.char).collect();
+
+    if mouse.just_pressed(MouseButton::Left) {
+        // clicking anywhere moves the focus to whatever was clicked, or nowhere
+        for (interaction, mut input, _, _) in query.iter_mut() {
+            let focused = *interaction != Interaction::None;
+            if input.focused != focused {
+                input.focused = focused;
+            }
+        }
+    }
+
+    for (_, mut input, mut color, children) in query.iter_mut() {
+        if input.focused {
+            let mut value = input.value.clone();
+            for c in typed.iter().filter(|c| !c.is_control()) {
+                value.push(*c);
+            }
+            if keys.just_pressed(KeyCode::Back) {
+                value.pop();
+            }
+            if keys.just_pressed(KeyCode::Return) || keys.just_pressed(KeyCode::Escape) {
+                input.focused = false;
+            }
+            if value != input.value {
+                input.value = value;
+            }
+        }
+
+        if !input.is_changed() {
+            continue;
+        }
+
+        *color = match input.focused {
+            true => theme.colors.hovered_button.into(),
+            false => theme.colors.normal_button.into(),
+        };
+
+        let shown = match (input.value.is_empty(), input.focused) {
+            (true, false) => input.placeholder.clone(),
+            (_, true) => format!("{}|", input.value),
+            (false, false) => input.value.clone(),
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = shown.clone();
+            }
+        }
+    }
+}
+
+// plain text at a position, used for anything that isnt a title
+pub fn spawn_text(
     commands: &mut Commands,
     text: &str,
-    font: Handle<Font>,
-    offset: f32,
+    theme: &Theme,
+    font_size: f32,
+    posx: f32,
+    posy: f32,
 ) -> Entity {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(posy),
+                    left: Val::Px(posx),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                text,
+                TextStyle {
+                    font: theme.font.clone(),
+                    font_size,
+                    color: theme.text_color,
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .id()
+}
+
+pub fn spawn_main_text(commands: &mut Commands, text: &str, theme: &Theme, offset: f32) -> Entity {
     commands
         .spawn_bundle(TextBundle {
             style: Style {
@@ -230,9 +540,9 @@ pub fn spawn_main_text(
             text: Text::with_section(
                 text,
                 TextStyle {
-                    font: font.clone(),
-                    font_size: 100.0,
-                    color: Color::WHITE,
+                    font: theme.font.clone(),
+                    font_size: theme.title_size,
+                    color: theme.text_color,
                 },
                 TextAlignment {
                     vertical: VerticalAlign::Center,
@@ -248,68 +558,97 @@ pub fn spawn_main_text(
 #[macro_export]
 macro_rules! spawn_button_grid {
     (
-        $commands:expr, $font:expr,
+        $commands:expr, $theme:expr,
         $(($but_type:expr, $text:expr)),+
     ) => { {
             let mut entities: Vec<Entity> = Vec::new();
+            let theme: &$crate::theme::Theme = $theme;
 
             let mut _y = 700.0;
 
-            $( entities.push(spawn_button($commands, $font, $text,40.0, 820.0, _y, Vec2::new(250.0, 100.0), $but_type)) ; _y -= 200.0;)+
+            $( entities.push(spawn_button($commands, theme, $text, theme.button_font_size, 820.0, _y, theme.button_size, $but_type)) ; _y -= 200.0;)+
             entities
     }};
 }
 
-// scroll the pre-game and deck selection menus
-pub fn scroll_backmap(
-    mut mouse_wheel_events: EventReader<MouseWheel>,
-    mut query: Query<&mut Style, With<Scrollable>>,
+// clicks on the board cards: left click completes or uncompletes the deck, shift-click starts or stops its split
+// and right click rerolls the card
+pub fn handle_ingame_input(
+    current_run_json: Res<CurrentRunJson>,
+    dialog_data: Res<DialogData>,
+    inspected: Res<Inspected>,
+    drag: Res<BoardDrag>,
+    mut run_actions: EventWriter<RunAction>,
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    camera_query: Query<&Transform, With<MainCamera>>,
+    ui_query: Query<&Interaction, With<Button>>,
+    card_query: Query<(&Transform, &DeckNumber, &BoardCard)>,
 ) {
-    for mouse_wheel_event in mouse_wheel_events.iter() {
-        for mut style in query.iter_mut() {
-            style.position.bottom += mouse_wheel_event.y * -70.0; // move up/down depending on how much the mouse moved (in reverse because it feels better)
-        }
+    // left clicks happen on release, unless the mouse was dragged to pan the board.
+    // dialog buttons close on press, so only count releases where the press was on the board too
+    let left = mouse.just_released(MouseButton::Left) && drag.on_board && !drag.dragged;
+    let right = mouse.just_pressed(MouseButton::Right);
+
+    if dialog_data.is_open() || inspected.pinned || (!left && !right) {
+        return;
     }
-}
 
-// scrolls the map during the actual game
-pub fn scroll_gamemap(
-    mut mouse_wheel_events: EventReader<MouseWheel>,
-    layout: Res<LayoutSettings>,
-    mut query: Query<&mut Transform, With<Scrollable>>,
-) {
-    let mul = 70.0;
+    if ui_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        // the click was meant for a ui button on top of the board
+        return;
+    }
 
-    for mouse_wheel_event in mouse_wheel_events.iter() {
-        for mut transform in query.iter_mut() {
-            if !layout.vertical {
-                transform.translation.y += mouse_wheel_event.y * -mul; // move up/down depending on how much the mouse moved (in reverse because it feels better)
-            } else {
-                transform.translation.x += mouse_wheel_event.y * mul;
-            }
+    let cursor = match cursor_to_world(&windows, camera_query.single()) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    let (deck, primary) = match card_under_cursor(cursor, card_query.iter()) {
+        Some(card) => card,
+        None => return,
+    };
+
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+
+    // the changes themselves happen in apply_run_actions, the same place the remote control api ends up
+    if left && shift {
+        // shift clicking a card starts or stops the deck's split
+        run_actions.send(RunAction::ToggleInProgress(deck));
+    } else if left {
+        // if its completed, uncomplete it
+        // if its not completed, complete it
+        if current_run_json.completed_decks[deck] {
+            run_actions.send(RunAction::Uncomplete(deck));
+        } else {
+            run_actions.send(RunAction::Complete(deck));
         }
+    } else {
+        // right click rerolls the card
+        run_actions.send(RunAction::Reroll { deck, primary });
     }
 }
 
-pub fn handle_ingame_input(
-    mut current_run_json: ResMut<CurrentRunJson>,
-    colors: Res<Colors>,
-    mut interaction_query: Query<
-        (&Interaction, &DeckNumber, &mut UiColor),
-        (Changed<Interaction>, With<Button>),
-    >,
+// ctrl+z to undo, ctrl+y or ctrl+shift+z to redo
+pub fn handle_undo_keys(
+    keys: Res<Input<KeyCode>>,
+    dialog_data: Res<DialogData>,
+    mut run_actions: EventWriter<RunAction>,
 ) {
-    for (interaction, deck_num, mut color) in interaction_query.iter_mut() {
-        if interaction == &Interaction::Clicked {
-            // if its enabled, disable it
-            // if its disabled it, enable it
-            if current_run_json.completed_decks[deck_num.num] {
-                current_run_json.complete_deck(deck_num.num);
-                *color = colors.disabled_deck.into();
-            } else {
-                current_run_json.decomplete_deck(deck_num.num);
-                *color = colors.enabled_deck.into();
-            }
-        }
+    let ctrl = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+
+    if !ctrl || dialog_data.is_open() {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Z) && !shift {
+        run_actions.send(RunAction::Undo);
+    } else if keys.just_pressed(KeyCode::Y) || (keys.just_pressed(KeyCode::Z) && shift) {
+        run_actions.send(RunAction::Redo);
     }
 }
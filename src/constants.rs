@@ -6,13 +6,15 @@ use crate::states_and_ui::GameState;
 
 use crate::handle_json::DeckDataWrapper;
 
-pub const NORMAL_BUTTON: Color = Color::rgb(0.45, 0.45, 0.45);
-pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+pub const NORMAL_BUTTON: Color = Color::PURPLE; // what settings.ini had before themes
+pub const HOVERED_BUTTON: Color = Color::PINK;
 pub const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35); // green
 pub const DISABLED_BUTTON: Color = Color::rgb(0.1, 0.1, 0.1); // same as clear
 
 pub const DISABLED_DECK: Color = Color::rgb(0.75, 0.35, 0.35); // red
 pub const ENABLED_DECK: Color = Color::rgb(0.35, 0.75, 0.35); // green
+pub const IN_PROGRESS_DECK: Color = Color::rgb(0.95, 0.85, 0.35); // yellow
+pub const LOCKED_DECK: Color = Color::rgb(0.35, 0.55, 0.85); // blue
 
 pub const CLEAR: Color = Color::rgb(0.1, 0.1, 0.1);
 pub const RESOLUTION: f32 = 16.0 / 9.0;
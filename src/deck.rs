@@ -4,7 +4,7 @@ use std::{fs, path::Path};
 
 use crate::{
     handle_json::{CurrentRunJson, DeckData, DeckDataWrapper},
-    states_and_ui::{DeckNumber, Scrollable},
+    states_and_ui::DeckNumber,
     CARD_H, CARD_W, SCALE,
 };
 
@@ -21,6 +21,12 @@ pub struct DecksTogether {
     pub name: String,
 }
 
+// a card on the in-game board, the deck it belongs to is stored in its DeckNumber
+#[derive(Component, Clone, Copy, Debug)]
+pub struct BoardCard {
+    pub primary: bool,
+}
+
 #[derive(Default, Clone)]
 pub struct DeckBacks {
     pub backs: Vec<Handle<Image>>,
@@ -42,21 +48,16 @@ pub fn make_decks(
     mut commands: Commands,
     assets: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    current_run_json: Res<CurrentRunJson>,
+    mut current_run_json: ResMut<CurrentRunJson>,
     deck_data: Res<DeckDataWrapper>,
 ) {
     let mut deck_vec: Vec<DecksTogether> = Vec::new();
 
-    let mut index = 0;
+    current_run_json.prepare(&deck_data);
 
-    for curr_json in deck_data.decks.clone() {
-        // if it is not enabled in the current run, dont add it
-        if !current_run_json.check_deck(&index) {
-            index += 1;
-            continue;
-        }
-
-        // if file hasnt already made a handle
+    // go through the decks in the order they were picked, so the board lines up with the run
+    for index in current_run_json.decks.clone() {
+        let curr_json = deck_data.decks[index].clone();
 
         let image: Handle<Image> = assets.load(&curr_json.file);
 
@@ -69,9 +70,7 @@ pub fn make_decks(
         );
         let atlas_handle = texture_atlases.add(atlas);
 
-        deck_vec.push(gen_2_decks(curr_json.clone(), atlas_handle.clone()));
-
-        index += 1;
+        deck_vec.push(gen_2_decks(curr_json, atlas_handle.clone()));
     }
     commands.insert_resource(Decks(deck_vec));
 }
@@ -103,8 +102,8 @@ pub fn spawn_card(
             },
             ..Default::default()
         })
-        .insert(DeckNumber { num: index })
-        .insert(Scrollable {}) // TODO make it not forced to be scrollable, this is just convenient
+        .insert(DeckNumber { num: deck_num })
+        .insert(BoardCard { primary })
         .id()
 }
 
@@ -0,0 +1,361 @@
+use bevy::prelude::*;
+
+use crate::{
+    button_input::{spawn_button, spawn_text_input, TextInput},
+    handle_json::{DeckData, DeckDataWrapper, EnabledJson, RunHistoryJson},
+    localization::Locale,
+    scroll::BackmapScroll,
+    states_and_ui::{grid_position, DeckNumber, GameState, MenuData, MenuItems, Scrollable},
+    stats::deck_stats,
+    theme::Theme,
+};
+
+pub struct DeckFilterPlugin;
+
+impl Plugin for DeckFilterPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DeckFilter::default())
+            .add_system_set(
+                SystemSet::on_enter(GameState::DeckSelection).with_system(setup_deck_filter),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::DeckSelection)
+                    .with_system(handle_filter_input)
+                    .with_system(update_filter_labels)
+                    .with_system(apply_deck_filter),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::PreGame).with_system(setup_pre_game_filter),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::PreGame)
+                    .with_system(handle_filter_input)
+                    .with_system(update_filter_labels),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterItems {
+    Show,
+    Tag,
+    Sort,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShowFilter {
+    All,
+    Enabled,
+    Disabled,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    Default, // the order in decks.json
+    Alphabetical,
+    RecentlyPlayed,
+    MostCompleted,
+}
+
+// what the deck selection grid is currently showing
+#[derive(Debug, Clone)]
+pub struct DeckFilter {
+    pub search: String,
+    pub show: ShowFilter,
+    pub tag: Option<String>,
+    pub sort: SortOrder,
+}
+
+impl Default for DeckFilter {
+    fn default() -> Self {
+        DeckFilter {
+            search: String::new(),
+            show: ShowFilter::All,
+            tag: None,
+            sort: SortOrder::Default,
+        }
+    }
+}
+
+impl DeckFilter {
+    pub fn matches_tag(&self, data: &DeckData) -> bool {
+        match &self.tag {
+            Some(tag) => data.all_tags().contains(tag),
+            None => true,
+        }
+    }
+
+    // the decks that pass the filter, in the order they should be shown
+    pub fn visible_decks(
+        &self,
+        deck_data: &DeckDataWrapper,
+        enabled_json: &EnabledJson,
+        run_history: &RunHistoryJson,
+        locale: &Locale,
+    ) -> Vec<usize> {
+        let search = self.search.to_lowercase();
+
+        let mut decks: Vec<usize> = (0..deck_data.decks.len())
+            .filter(|deck| {
+                let data = &deck_data.decks[*deck];
+
+                let shown = match self.show {
+                    ShowFilter::All => true,
+                    ShowFilter::Enabled => !enabled_json.check_disabled(deck),
+                    ShowFilter::Disabled => enabled_json.check_disabled(deck),
+                };
+                shown
+                    && self.matches_tag(data)
+                    && data
+                        .display_name(&locale.language)
+                        .to_lowercase()
+                        .contains(&search)
+            })
+            .collect();
+
+        match self.sort {
+            SortOrder::Default => {}
+            SortOrder::Alphabetical => decks.sort_by_key(|deck| {
+                deck_data.decks[*deck]
+                    .display_name(&locale.language)
+                    .to_lowercase()
+            }),
+            SortOrder::RecentlyPlayed => {
+                let stats = deck_stats(run_history);
+                decks.sort_by_key(|deck| {
+                    std::cmp::Reverse(stats.get(deck).map(|stats| stats.last_played))
+                });
+            }
+            SortOrder::MostCompleted => {
+                let stats = deck_stats(run_history);
+                decks.sort_by_key(|deck| {
+                    std::cmp::Reverse(stats.get(deck).map(|stats| stats.completions))
+                });
+            }
+        }
+        decks
+    }
+}
+
+impl ShowFilter {
+    fn next(self) -> Self {
+        match self {
+            ShowFilter::All => ShowFilter::Enabled,
+            ShowFilter::Enabled => ShowFilter::Disabled,
+            ShowFilter::Disabled => ShowFilter::All,
+        }
+    }
+
+    // the lang file key for the button
+    fn key(self) -> &'static str {
+        match self {
+            ShowFilter::All => "filter.show_all",
+            ShowFilter::Enabled => "filter.show_enabled",
+            ShowFilter::Disabled => "filter.show_disabled",
+        }
+    }
+}
+
+impl SortOrder {
+    fn next(self) -> Self {
+        match self {
+            SortOrder::Default => SortOrder::Alphabetical,
+            SortOrder::Alphabetical => SortOrder::RecentlyPlayed,
+            SortOrder::RecentlyPlayed => SortOrder::MostCompleted,
+            SortOrder::MostCompleted => SortOrder::Default,
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            SortOrder::Default => "filter.sort_default",
+            SortOrder::Alphabetical => "filter.sort_alphabetical",
+            SortOrder::RecentlyPlayed => "filter.sort_recent",
+            SortOrder::MostCompleted => "filter.sort_completed",
+        }
+    }
+}
+
+// every tag used by at least one deck, sorted
+pub fn all_tags(deck_data: &DeckDataWrapper) -> Vec<String> {
+    let mut tags: Vec<String> = deck_data
+        .decks
+        .iter()
+        .flat_map(|deck| deck.all_tags())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+#[derive(Component)]
+pub struct SearchBox {
+    // the text input used for searching decks
+}
+
+fn setup_deck_filter(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    mut menu_data: ResMut<MenuData>,
+    mut filter: ResMut<DeckFilter>,
+) {
+    *filter = DeckFilter::default();
+
+    let size = Vec2::new(250.0, 80.0);
+
+    let search = spawn_text_input(
+        &mut commands,
+        &theme,
+        &locale.get("filter.search"),
+        30.0,
+        10.0,
+        800.0,
+        size,
+    );
+    commands.entity(search).insert(SearchBox {});
+    menu_data.button_entity.push(search);
+
+    let buttons = [
+        (FilterItems::Show, filter.show.key(), 700.0),
+        (FilterItems::Tag, "filter.tag_any", 600.0),
+        (FilterItems::Sort, filter.sort.key(), 500.0),
+    ];
+    for (item, key, y) in buttons {
+        menu_data.button_entity.push(spawn_button(
+            &mut commands,
+            &theme,
+            &locale.get(key),
+            30.0,
+            10.0,
+            y,
+            size,
+            MenuItems::Filter(item),
+        ));
+    }
+}
+
+fn setup_pre_game_filter(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    mut menu_data: ResMut<MenuData>,
+    mut filter: ResMut<DeckFilter>,
+) {
+    *filter = DeckFilter::default();
+
+    let size = Vec2::new(250.0, 80.0);
+
+    menu_data.button_entity.push(spawn_button(
+        &mut commands,
+        &theme,
+        &locale.get("filter.tag_any"),
+        30.0,
+        10.0,
+        700.0,
+        size,
+        MenuItems::Filter(FilterItems::Tag),
+    ));
+}
+
+fn handle_filter_input(
+    mut filter: ResMut<DeckFilter>,
+    deck_data: Res<DeckDataWrapper>,
+    search_query: Query<&TextInput, (With<SearchBox>, Changed<TextInput>)>,
+    query: Query<(&Interaction, &MenuItems), (With<Button>, Changed<Interaction>)>,
+) {
+    for input in search_query.iter() {
+        if filter.search != input.value {
+            filter.search = input.value.clone();
+        }
+    }
+
+    for (interaction, menu_item) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match menu_item {
+            MenuItems::Filter(FilterItems::Show) => filter.show = filter.show.next(),
+            MenuItems::Filter(FilterItems::Sort) => filter.sort = filter.sort.next(),
+            MenuItems::Filter(FilterItems::Tag) => {
+                // cycle through every tag, then back to any tag
+                let tags = all_tags(&deck_data);
+                let next = match &filter.tag {
+                    Some(tag) => tags.iter().position(|x| x == tag).map(|i| i + 1),
+                    None => Some(0),
+                };
+                filter.tag = next.and_then(|i| tags.get(i).cloned());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_filter_labels(
+    filter: Res<DeckFilter>,
+    locale: Res<Locale>,
+    query: Query<(&MenuItems, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !filter.is_changed() {
+        return;
+    }
+
+    for (menu_item, children) in query.iter() {
+        let label = match menu_item {
+            MenuItems::Filter(FilterItems::Show) => locale.get(filter.show.key()),
+            MenuItems::Filter(FilterItems::Sort) => locale.get(filter.sort.key()),
+            MenuItems::Filter(FilterItems::Tag) => match &filter.tag {
+                Some(tag) => locale.format("filter.tag", &[tag]),
+                None => locale.get("filter.tag_any"),
+            },
+            _ => continue,
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+// hide the decks that dont match and move the rest so the grid has no holes
+fn apply_deck_filter(
+    filter: Res<DeckFilter>,
+    deck_data: Res<DeckDataWrapper>,
+    enabled_json: Res<EnabledJson>,
+    run_history: Res<RunHistoryJson>,
+    locale: Res<Locale>,
+    mut scroll: ResMut<BackmapScroll>,
+    mut query: Query<(&DeckNumber, &mut Style), With<Button>>,
+    mut title_query: Query<&mut Style, (With<Scrollable>, Without<DeckNumber>)>,
+) {
+    if !filter.is_changed() && !enabled_json.is_changed() && !locale.is_changed() {
+        return;
+    }
+
+    let visible = filter.visible_decks(&deck_data, &enabled_json, &run_history, &locale);
+
+    // the grid might be shorter now, keep the title lined up if that scrolled it back up
+    let offset = scroll.offset;
+    scroll.fit_rows(visible.len());
+    if scroll.offset != offset {
+        for mut style in title_query.iter_mut() {
+            style.position.bottom += scroll.offset - offset;
+        }
+    }
+
+    for (deck_num, mut style) in query.iter_mut() {
+        match visible.iter().position(|deck| *deck == deck_num.num) {
+            Some(index) => {
+                let position = grid_position(index);
+                style.display = Display::Flex;
+                style.position.left = Val::Px(position.x);
+                style.position.bottom = Val::Px(position.y + scroll.offset);
+            }
+            None => style.display = Display::None,
+        }
+    }
+}
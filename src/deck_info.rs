@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::{
+    handle_json::{DeckDataWrapper, RunHistoryJson},
+    localization::Locale,
+    states_and_ui::{DeckNumber, GameState, MenuData},
+    stats::deck_stats,
+    theme::Theme,
+};
+
+pub struct DeckInfoPlugin;
+
+impl Plugin for DeckInfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::DeckSelection).with_system(setup_deck_tooltip),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::DeckSelection).with_system(update_deck_tooltip),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::PreGame).with_system(setup_deck_tooltip))
+        .add_system_set(SystemSet::on_update(GameState::PreGame).with_system(update_deck_tooltip));
+    }
+}
+
+#[derive(Component)]
+pub struct DeckTooltip {
+    // the hover card that follows the cursor while a deck is hovered
+    deck: Option<usize>, // the deck the text was last made for
+}
+
+fn setup_deck_tooltip(mut commands: Commands, theme: Res<Theme>, mut menu_data: ResMut<MenuData>) {
+    let tooltip = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                padding: Rect::all(Val::Px(10.0)),
+                display: Display::None,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    "",
+                    TextStyle {
+                        font: theme.font.clone(),
+                        font_size: 28.0,
+                        color: theme.text_color,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        })
+        .insert(DeckTooltip { deck: None })
+        .id();
+
+    menu_data.button_entity.push(tooltip);
+}
+
+// everything worth knowing about a deck before picking it
+pub fn deck_card_text(
+    deck_data: &DeckDataWrapper,
+    run_history: &RunHistoryJson,
+    deck: usize,
+    locale: &Locale,
+) -> String {
+    let data = &deck_data.decks[deck];
+
+    let mut text = data.describe(locale);
+    text += "\n";
+    text += &locale.format(
+        "deck_info.cards",
+        &[&data.primary_cards, &data.secondary_cards],
+    );
+
+    text += "\n";
+    match deck_stats(run_history).get(&deck) {
+        Some(stats) => {
+            text += &locale.format(
+                "deck_info.played",
+                &[
+                    &stats.plays,
+                    &stats.completions,
+                    &format!("{:.0}", stats.win_rate()),
+                ],
+            );
+        }
+        None => text += &locale.get("deck_info.never_played"),
+    }
+    text
+}
+
+fn update_deck_tooltip(
+    windows: Res<Windows>,
+    deck_data: Res<DeckDataWrapper>,
+    run_history: Res<RunHistoryJson>,
+    locale: Res<Locale>,
+    buttons: Query<(&Interaction, &DeckNumber), With<Button>>,
+    mut tooltip_query: Query<(&mut DeckTooltip, &mut Style, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    let hovered = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Hovered)
+        .map(|(_, deck_num)| deck_num.num);
+
+    let cursor = windows
+        .get_primary()
+        .and_then(|window| window.cursor_position());
+
+    for (mut tooltip, mut style, children) in tooltip_query.iter_mut() {
+        let (deck, cursor) = match (hovered, cursor) {
+            (Some(deck), Some(cursor)) => (deck, cursor),
+            _ => {
+                style.display = Display::None;
+                continue;
+            }
+        };
+
+        // ui positions start at the bottom left, same as the cursor
+        style.display = Display::Flex;
+        style.position.left = Val::Px(cursor.x + 20.0);
+        style.position.bottom = Val::Px(cursor.y + 20.0);
+
+        if tooltip.deck == Some(deck) {
+            // the text is already right
+            continue;
+        }
+        tooltip.deck = Some(deck);
+
+        let card_text = deck_card_text(&deck_data, &run_history, deck, &locale);
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = card_text.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::{fs, path::PathBuf};
+
+use bevy::{prelude::*, render::render_resource::TextureFormat};
+use image::{imageops, Rgba, RgbaImage};
+use serde::Serialize;
+
+use crate::{
+    button_input::spawn_button,
+    constants::{CARD_H, CARD_W},
+    deck::{Deck, Decks},
+    handle_json::{CurrentRunJson, DeckData, DeckDataWrapper},
+    localization::Locale,
+    states_and_ui::{DialogItems, GameState, MenuData, MenuItems},
+    theme::Theme,
+    timer::{format_timer, RunClock},
+};
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_export))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(handle_export_buttons.label("run_export")),
+            );
+    }
+}
+
+// everything worth sharing about a run
+#[derive(Serialize, Debug, Clone)]
+pub struct RunSummary {
+    pub seed: u64,
+    pub score: usize,
+    pub time: f64, // run clock in seconds
+    pub started: u64,
+    pub finished: bool,
+    pub decks: Vec<DeckSummary>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DeckSummary {
+    pub deck: usize,
+    pub name: String,
+    pub primary: CardSummary,
+    pub secondary: CardSummary,
+    pub completed: bool,
+    pub in_progress: bool,
+    pub time: f64, // split in seconds
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CardSummary {
+    pub index: usize,
+    pub text: Option<String>,
+}
+
+impl RunSummary {
+    pub fn new(run: &CurrentRunJson, deck_data: &DeckDataWrapper) -> Self {
+        let mut decks = Vec::new();
+
+        for (i, deck) in run.decks.iter().enumerate() {
+            let data = &deck_data.decks[*deck];
+            let drawn = run.drawn.get(i).copied().unwrap_or_default();
+
+            decks.push(DeckSummary {
+                deck: *deck,
+                name: data.name.clone(),
+                primary: CardSummary::new(data, true, drawn.primary),
+                secondary: CardSummary::new(data, false, drawn.secondary),
+                completed: run.completed_decks.get(i).copied().unwrap_or(false),
+                in_progress: run.timers.current == Some(i),
+                time: run
+                    .timers
+                    .splits
+                    .get(i)
+                    .map(|split| split.time)
+                    .unwrap_or(0.0),
+            });
+        }
+
+        RunSummary {
+            seed: run.seed,
+            score: run.score,
+            time: run.timers.run,
+            started: run.started,
+            finished: run.is_finished(),
+            decks,
+        }
+    }
+
+    // the times as the clock has them right now, for while the run is being played
+    pub fn with_clock(mut self, clock: &RunClock, run: &CurrentRunJson) -> Self {
+        self.time = clock.run;
+        for (i, deck) in self.decks.iter_mut().enumerate() {
+            deck.time = clock.split_time(run, i);
+        }
+        self
+    }
+
+    // deck names are shown in the given language, the json keeps the untranslated ones
+    pub fn to_markdown(&self, deck_data: &DeckDataWrapper, language: &str) -> String {
+        let mut markdown = format!(
+            "# Pyramid run\n\nScore: {}  \nTime: {}  \nSeed: {}\n\n",
+            self.score,
+            format_timer(self.time),
+            self.seed
+        );
+
+        markdown += "| # | Deck | Primary | Secondary | Status | Time |\n";
+        markdown += "|---|------|---------|-----------|--------|------|\n";
+
+        for (i, deck) in self.decks.iter().enumerate() {
+            let status = if deck.completed {
+                "Completed"
+            } else if deck.in_progress {
+                "In progress"
+            } else {
+                "Not completed"
+            };
+
+            markdown += &format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                i + 1,
+                escape_markdown(deck_data.decks[deck.deck].display_name(language)),
+                deck.primary.to_markdown(),
+                deck.secondary.to_markdown(),
+                status,
+                format_timer(deck.time)
+            );
+        }
+        markdown
+    }
+}
+
+impl CardSummary {
+    pub fn new(data: &DeckData, primary: bool, index: usize) -> Self {
+        CardSummary {
+            index,
+            text: data.card_text(primary, index).map(String::from),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        match &self.text {
+            Some(text) => escape_markdown(text),
+            None => format!("Card #{}", self.index + 1),
+        }
+    }
+}
+
+// keep card text from breaking the table
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn setup_export(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    mut menu_data: ResMut<MenuData>,
+) {
+    menu_data.button_entity.push(spawn_button(
+        &mut commands,
+        &theme,
+        &locale.get("export.export"),
+        theme.button_font_size,
+        1400.0,
+        780.0,
+        Vec2::new(200.0, 100.0),
+        MenuItems::ExportRun,
+    ));
+}
+
+fn handle_export_buttons(
+    mut current_run_json: ResMut<CurrentRunJson>,
+    mut clock: ResMut<RunClock>,
+    deck_data: Res<DeckDataWrapper>,
+    locale: Res<Locale>,
+    decks: Res<Decks>,
+    atlases: Res<Assets<TextureAtlas>>,
+    images: Res<Assets<Image>>,
+    query: Query<(&Interaction, &MenuItems), (With<Button>, Changed<Interaction>)>,
+) {
+    for (interaction, menu_item) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            // finishing the run from the dialog throws it away, so this has to happen before handle_ui_buttons
+            if matches!(
+                menu_item,
+                MenuItems::ExportRun | MenuItems::Dialog(DialogItems::ExportAndFinish)
+            ) {
+                clock.write(&mut current_run_json);
+                match export_run(
+                    &current_run_json,
+                    &deck_data,
+                    &locale.language,
+                    &decks,
+                    &atlases,
+                    &images,
+                ) {
+                    Ok(path) => println!("Exported run to {}", path.display()),
+                    Err(err) => println!("Could not export run: {}", err),
+                }
+            }
+        }
+    }
+}
+
+// writes exports/run_<seed>.md, .json and .png, returns the path without an extension
+pub fn export_run(
+    run: &CurrentRunJson,
+    deck_data: &DeckDataWrapper,
+    language: &str,
+    decks: &Decks,
+    atlases: &Assets<TextureAtlas>,
+    images: &Assets<Image>,
+) -> std::io::Result<PathBuf> {
+    let summary = RunSummary::new(run, deck_data);
+
+    fs::create_dir_all("exports")?;
+    let path = PathBuf::from(format!("exports/run_{}", run.seed));
+
+    fs::write(
+        path.with_extension("md"),
+        summary.to_markdown(deck_data, language),
+    )?;
+    fs::write(
+        path.with_extension("json"),
+        serde_json::to_string_pretty(&summary)?,
+    )?;
+
+    render_board(run, decks, atlases, images)
+        .save(path.with_extension("png"))
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+
+    Ok(path)
+}
+
+// draws every deck's drawn cards side by side, two decks per row like on the board
+pub fn render_board(
+    run: &CurrentRunJson,
+    decks: &Decks,
+    atlases: &Assets<TextureAtlas>,
+    images: &Assets<Image>,
+) -> RgbaImage {
+    // CARD_H is the width of a card, CARD_W is the height
+    let card_w = CARD_H as u32;
+    let card_h = CARD_W as u32;
+    let gap = 20;
+    let columns = 2;
+    let rows = (decks.0.len() as u32).div_ceil(columns);
+
+    let mut board = RgbaImage::from_pixel(
+        columns * (card_w * 2 + gap) + gap,
+        rows.max(1) * (card_h + gap) + gap,
+        Rgba([25, 25, 25, 255]), // same as CLEAR
+    );
+
+    for (i, together) in decks.0.iter().enumerate() {
+        let x = gap + (i as u32 % columns) * (card_w * 2 + gap);
+        let y = gap + (i as u32 / columns) * (card_h + gap);
+        let drawn = run.drawn.get(i).copied().unwrap_or_default();
+
+        let cards = [
+            (&together.primary, drawn.primary),
+            (&together.secondary, drawn.secondary),
+        ];
+        for (j, (deck, index)) in cards.iter().enumerate() {
+            // cards that havent loaded yet are left empty
+            if let Some(card) = card_image(deck, *index, atlases, images) {
+                imageops::overlay(&mut board, &card, x + j as u32 * card_w, y);
+            }
+        }
+
+        if run.completed_decks.get(i).copied().unwrap_or(false) {
+            draw_border(
+                &mut board,
+                x,
+                y,
+                card_w * 2,
+                card_h,
+                Rgba([89, 191, 89, 255]), // same as ENABLED_DECK
+            );
+        }
+    }
+    board
+}
+
+// cut a single card out of its deck's sprite sheet
+fn card_image(
+    deck: &Deck,
+    index: usize,
+    atlases: &Assets<TextureAtlas>,
+    images: &Assets<Image>,
+) -> Option<RgbaImage> {
+    let atlas = atlases.get(&deck.sheet)?;
+    let image = images.get(&atlas.texture)?;
+
+    match image.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => {}
+        _ => return None,
+    }
+
+    let size = image.texture_descriptor.size;
+    let sheet = RgbaImage::from_raw(size.width, size.height, image.data.clone())?;
+    let rect = atlas.textures.get(index + deck.offset)?;
+
+    Some(
+        imageops::crop_imm(
+            &sheet,
+            rect.min.x as u32,
+            rect.min.y as u32,
+            rect.width() as u32,
+            rect.height() as u32,
+        )
+        .to_image(),
+    )
+}
+
+fn draw_border(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    let thickness = 8;
+
+    for px in x..(x + width).min(image.width()) {
+        for py in y..(y + height).min(image.height()) {
+            let edge = px < x + thickness
+                || py < y + thickness
+                || px >= x + width - thickness
+                || py >= y + height - thickness;
+            if edge {
+                image.put_pixel(px, py, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+
+use bevy::{prelude::*, ui::UiSystem};
+
+use crate::{settings::Settings, theme::Theme};
+
+pub struct UiFontPlugin;
+
+impl Plugin for UiFontPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(UiFont::default())
+            .add_system(update_ui_scale)
+            .add_system(follow_theme_font)
+            // before the ui gets laid out, so new text never shows up at the wrong size
+            .add_system_to_stage(CoreStage::PostUpdate, apply_ui_font.before(UiSystem::Flex));
+    }
+}
+
+// the screen size every menu was laid out for
+const DESIGN_WIDTH: f32 = 1920.0;
+const DESIGN_HEIGHT: f32 = 1080.0;
+pub const TEXT_SCALES: [f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+
+// the font all text uses, and how much to scale it by
+pub struct UiFont {
+    pub font: Handle<Font>,
+    pub file: Option<String>, // a font picked in the settings, None uses the theme's font
+    pub text_scale: f32,      // set in the settings
+    pub ui_scale: f32,        // shrinks text on screens smaller than the design size
+}
+
+impl Default for UiFont {
+    fn default() -> Self {
+        UiFont {
+            font: Handle::default(),
+            file: None,
+            text_scale: 1.0,
+            ui_scale: 1.0,
+        }
+    }
+}
+
+impl UiFont {
+    pub fn scale(&self) -> f32 {
+        self.text_scale * self.ui_scale
+    }
+
+    // reads the [Font] section of settings.ini
+    pub fn load(&mut self, settings: &Settings, theme: &Theme, asset_server: &AssetServer) {
+        self.file = settings
+            .settings
+            .get("Font", "file")
+            .filter(|file| !file.is_empty());
+        if let Ok(Some(scale)) = settings.settings.getfloat("Font", "text_scale") {
+            self.text_scale = (scale as f32).clamp(TEXT_SCALES[0], TEXT_SCALES[4]);
+        }
+        self.set_file(self.file.clone(), theme, asset_server);
+    }
+
+    pub fn save(&self, settings: &mut Settings) {
+        settings
+            .settings
+            .set("Font", "file", Some(self.file.clone().unwrap_or_default()));
+        settings
+            .settings
+            .set("Font", "text_scale", Some(self.text_scale.to_string()));
+        settings.update();
+    }
+
+    pub fn set_file(&mut self, file: Option<String>, theme: &Theme, asset_server: &AssetServer) {
+        self.font = match &file {
+            Some(file) => asset_server.load(file.as_str()),
+            None => theme.font.clone(),
+        };
+        self.file = file;
+    }
+
+    // the file name shown on the font button, None when using the theme's font
+    pub fn file_name(&self) -> Option<&str> {
+        let file = self.file.as_deref()?;
+        file.rsplit(&['/', '\\'][..]).next()
+    }
+}
+
+// every .ttf and .otf in assets/fonts, so fonts can just be dropped in there
+pub fn font_files() -> Vec<String> {
+    let mut files: Vec<String> = match fs::read_dir("assets/fonts") {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_str()?.to_owned();
+                let lower = name.to_lowercase();
+                match lower.ends_with(".ttf") || lower.ends_with(".otf") {
+                    true => Some(format!("fonts/{}", name)),
+                    false => None,
+                }
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort_by_key(|file| file.to_lowercase());
+    files
+}
+
+#[derive(Component)]
+pub struct BaseFontSize {
+    // the sizes the text was spawned with, one for each section
+    sizes: Vec<f32>,
+}
+
+fn update_ui_scale(windows: Res<Windows>, mut ui_font: ResMut<UiFont>) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let scale = (window.width() / DESIGN_WIDTH)
+        .min(window.height() / DESIGN_HEIGHT)
+        .min(1.0);
+    if (scale - ui_font.ui_scale).abs() > f32::EPSILON {
+        ui_font.ui_scale = scale;
+    }
+}
+
+// switching themes changes the font too, unless one was picked in the settings
+fn follow_theme_font(theme: Res<Theme>, mut ui_font: ResMut<UiFont>) {
+    if theme.is_changed() && ui_font.file.is_none() && ui_font.font != theme.font {
+        ui_font.font = theme.font.clone();
+    }
+}
+
+// scale every piece of ui text and give it the font, new text gets its size remembered first
+// text on the board (Text2d) is sized for the world, so it's left alone
+fn apply_ui_font(
+    mut commands: Commands,
+    ui_font: Res<UiFont>,
+    mut new_query: Query<(Entity, &mut Text), (With<Node>, Without<BaseFontSize>)>,
+    mut query: Query<(&mut Text, &BaseFontSize), With<Node>>,
+) {
+    let scale = ui_font.scale();
+
+    for (entity, mut text) in new_query.iter_mut() {
+        let sizes = text
+            .sections
+            .iter()
+            .map(|section| section.style.font_size)
+            .collect();
+        for section in text.sections.iter_mut() {
+            section.style.font = ui_font.font.clone();
+            section.style.font_size *= scale;
+        }
+        commands.entity(entity).insert(BaseFontSize { sizes });
+    }
+
+    if !ui_font.is_changed() {
+        return;
+    }
+    for (mut text, base) in query.iter_mut() {
+        for (section, size) in text.sections.iter_mut().zip(base.sizes.iter()) {
+            section.style.font = ui_font.font.clone();
+            section.style.font_size = size * scale;
+        }
+    }
+}
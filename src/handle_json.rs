@@ -1,21 +1,39 @@
-use std::{fs::File, path::Path};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 
+use crate::localization::Locale;
+
 impl Plugin for JsonPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(EnabledJson {
             enabled: Vec::new(),
             disabled: Vec::new(),
+            saved: Vec::new(),
         })
         .insert_resource(CurrentRunJson {
             score: 0,
             decks: Vec::new(),
             hand: Vec::new(),
             completed_decks: Vec::new(),
+            drawn: Vec::new(),
+            history: ActionHistory::default(),
+            seed: 0,
+            started: 0,
+            timers: RunTimers::default(),
         })
-        .insert_resource(DeckDataWrapper { decks: Vec::new() });
+        .insert_resource(DeckDataWrapper { decks: Vec::new() })
+        .insert_resource(RunHistoryJson::default())
+        .insert_resource(PresetsJson::default())
+        .insert_resource(ThemesJson::default());
     }
 }
 
@@ -32,9 +50,10 @@ impl EnabledJson {
         let file_path = Path::new("config/enabled_decks.json");
         let reader = File::open(file_path).unwrap(); // open in read only
         *self = serde_json::from_reader(reader).unwrap();
+        self.saved = self.sorted_enabled();
     }
 
-    pub fn update(&self) {
+    pub fn update(&mut self) {
         let file_path = Path::new("config/enabled_decks.json");
         let writer = File::options()
             .write(true)
@@ -42,7 +61,19 @@ impl EnabledJson {
             .open(file_path)
             .unwrap(); // open with write perms
         serde_json::to_writer(writer, self).unwrap();
+        self.saved = self.sorted_enabled();
+    }
+    // false if there are changes that havent been written to config/enabled_decks.json yet
+    pub fn is_saved(&self) -> bool {
+        self.sorted_enabled() == self.saved
+    }
+
+    fn sorted_enabled(&self) -> Vec<usize> {
+        let mut enabled = self.enabled.clone();
+        enabled.sort_unstable();
+        enabled
     }
+
     pub fn enable(&mut self, deck: usize) {
         self.enabled.push(deck);
 
@@ -71,11 +102,18 @@ impl EnabledJson {
     }
 }
 impl CurrentRunJson {
-    pub fn load(&mut self) {
-        // load json into the struct
+    // load json into the struct, left as it was if the save is missing, broken or from an older version
+    pub fn load(&mut self) -> std::io::Result<()> {
         let file_path = Path::new("config/current_run.json");
-        let reader = File::open(file_path).unwrap(); // open in read only mode
-        *self = serde_json::from_reader(reader).unwrap();
+        let reader = File::open(file_path)?; // open in read only mode
+        *self = serde_json::from_reader(reader)?;
+        Ok(())
+    }
+
+    // the saved run, None if there is no save or it cant be read
+    pub fn read_saved() -> Option<CurrentRunJson> {
+        let reader = File::open("config/current_run.json").ok()?;
+        serde_json::from_reader(reader).ok()
     }
 
     pub fn update(&self) {
@@ -83,6 +121,7 @@ impl CurrentRunJson {
         let file_path = Path::new("config/current_run.json");
         let writer = File::options()
             .write(true)
+            .create(true) // the save is deleted when starting a new game
             .truncate(true)
             .open(file_path)
             .unwrap(); // open with write perms
@@ -100,7 +139,7 @@ impl CurrentRunJson {
     pub fn disable_deck(&mut self, deck: usize) {
         if self.check_deck(&deck) {
             let i = self.decks.iter().position(|x| *x == deck).unwrap(); // taken from https://stackoverflow.com/a/26243276/17942630
-            self.decks.remove(i);
+            self.remove_deck(i);
         } else {
             println!("Could not find value {} in json", deck);
         }
@@ -111,27 +150,619 @@ impl CurrentRunJson {
 
     pub fn complete_deck(&mut self, deck: usize) {
         self.completed_decks[deck] = true;
+        self.update_score();
+
+        // completing a deck stops its split
+        if self.timers.current == Some(deck) {
+            self.timers.current = None;
+        }
+        if let Some(split) = self.timers.splits.get_mut(deck) {
+            split.finished_at = Some(self.timers.run);
+        }
     }
 
     pub fn decomplete_deck(&mut self, deck: usize) {
         self.completed_decks[deck] = false;
+        self.update_score();
+
+        if let Some(split) = self.timers.splits.get_mut(deck) {
+            split.finished_at = None;
+        }
+    }
+
+    // mark a deck as the one being played, or stop it if it already is
+    pub fn toggle_in_progress(&mut self, deck: usize) {
+        if self.timers.current == Some(deck) {
+            self.timers.current = None;
+        } else if !self.completed_decks[deck] {
+            self.timers.current = Some(deck);
+        }
+    }
+
+    // one point for every completed deck
+    fn update_score(&mut self) {
+        self.score = self.completed_decks.iter().filter(|x| **x).count();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.decks.is_empty()
+            && self.completed_decks.len() == self.decks.len()
+            && self.completed_decks.iter().all(|x| *x)
+    }
+
+    // make sure every deck in the run has a completion flag and drawn cards
+    pub fn prepare(&mut self, deck_data: &DeckDataWrapper) {
+        if self.seed == 0 {
+            // new run (or a save from before seeds existed)
+            self.seed = rand::thread_rng().gen_range(1..u64::MAX);
+        }
+        if self.started == 0 {
+            self.started = unix_time();
+        }
+
+        self.completed_decks.resize(self.decks.len(), false);
+        self.timers
+            .splits
+            .resize(self.decks.len(), Split::default());
+        self.drawn.truncate(self.decks.len());
+
+        for i in self.drawn.len()..self.decks.len() {
+            // the same seed and decks always draw the same cards
+            let mut rng = StdRng::seed_from_u64(self.seed ^ self.decks[i] as u64);
+            let data = &deck_data.decks[self.decks[i]];
+            self.drawn.push(DrawnCards {
+                primary: rng.gen_range(0..data.primary_cards.max(1)),
+                secondary: rng.gen_range(0..data.secondary_cards.max(1)),
+            });
+        }
+    }
+
+    // builds the command for toggling a deck in the pre-game selection
+    pub fn toggle_deck_command(&self, deck: usize) -> RunCommand {
+        match self.decks.iter().position(|x| *x == deck) {
+            Some(index) => RunCommand::DisableDeck {
+                deck,
+                index,
+                completed: self.completed_decks.get(index).copied().unwrap_or(false),
+                drawn: self.drawn.get(index).copied(),
+            },
+            None => RunCommand::EnableDeck {
+                deck,
+                index: self.decks.len(),
+                completed: false,
+                drawn: None,
+            },
+        }
+    }
+
+    // run a command and remember it so it can be undone
+    pub fn apply(&mut self, command: RunCommand) {
+        self.run(command);
+        self.history.undo.push(command);
+        self.history.redo.clear();
+    }
+
+    // undo the last command, deck selection can only be undone before the game starts
+    pub fn undo(&mut self, allow_selection: bool) -> bool {
+        match self.history.undo.last() {
+            Some(command) if allow_selection || !command.is_selection() => {
+                let command = self.history.undo.pop().unwrap();
+                self.run(command.inverse());
+                self.history.redo.push(command);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn redo(&mut self, allow_selection: bool) -> bool {
+        match self.history.redo.last() {
+            Some(command) if allow_selection || !command.is_selection() => {
+                let command = self.history.redo.pop().unwrap();
+                self.run(command);
+                self.history.undo.push(command);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn run(&mut self, command: RunCommand) {
+        match command {
+            RunCommand::EnableDeck {
+                deck,
+                index,
+                completed,
+                drawn,
+            } => self.insert_deck(deck, index, completed, drawn),
+            RunCommand::DisableDeck { index, .. } => self.remove_deck(index),
+            RunCommand::CompleteDeck(deck) => self.complete_deck(deck),
+            RunCommand::UncompleteDeck(deck) => self.decomplete_deck(deck),
+            RunCommand::Reroll {
+                deck, primary, to, ..
+            } => {
+                if primary {
+                    self.drawn[deck].primary = to;
+                } else {
+                    self.drawn[deck].secondary = to;
+                }
+            }
+        }
+    }
+
+    fn insert_deck(
+        &mut self,
+        deck: usize,
+        index: usize,
+        completed: bool,
+        drawn: Option<DrawnCards>,
+    ) {
+        // only touch the per deck lists if they were already in sync with the decks
+        let completed_synced = self.completed_decks.len() == self.decks.len();
+        let drawn_synced = self.drawn.len() == self.decks.len();
+        let splits_synced = self.timers.splits.len() == self.decks.len();
+
+        self.decks.insert(index, deck);
+        self.timers.current = None;
+
+        if splits_synced {
+            self.timers.splits.insert(index, Split::default());
+        }
+        if completed_synced {
+            self.completed_decks.insert(index, completed);
+        }
+        if let (true, Some(drawn)) = (drawn_synced, drawn) {
+            self.drawn.insert(index, drawn);
+        }
+    }
+
+    fn remove_deck(&mut self, index: usize) {
+        self.decks.remove(index);
+        self.timers.current = None;
+
+        if index < self.timers.splits.len() {
+            self.timers.splits.remove(index);
+        }
+        if index < self.completed_decks.len() {
+            self.completed_decks.remove(index);
+        }
+        if index < self.drawn.len() {
+            self.drawn.remove(index);
+        }
+    }
+}
+
+impl RunCommand {
+    pub fn inverse(&self) -> RunCommand {
+        match *self {
+            RunCommand::EnableDeck {
+                deck,
+                index,
+                completed,
+                drawn,
+            } => RunCommand::DisableDeck {
+                deck,
+                index,
+                completed,
+                drawn,
+            },
+            RunCommand::DisableDeck {
+                deck,
+                index,
+                completed,
+                drawn,
+            } => RunCommand::EnableDeck {
+                deck,
+                index,
+                completed,
+                drawn,
+            },
+            RunCommand::CompleteDeck(deck) => RunCommand::UncompleteDeck(deck),
+            RunCommand::UncompleteDeck(deck) => RunCommand::CompleteDeck(deck),
+            RunCommand::Reroll {
+                deck,
+                primary,
+                from,
+                to,
+            } => RunCommand::Reroll {
+                deck,
+                primary,
+                from: to,
+                to: from,
+            },
+        }
+    }
+
+    // commands that change which decks are in the run
+    pub fn is_selection(&self) -> bool {
+        matches!(
+            self,
+            RunCommand::EnableDeck { .. } | RunCommand::DisableDeck { .. }
+        )
+    }
+}
+
+impl RunHistoryJson {
+    pub fn load(&mut self) {
+        let file_path = Path::new("config/run_history.json");
+        if !file_path.exists() {
+            // no runs have been finished yet
+            return;
+        }
+        let reader = File::open(file_path).unwrap(); // open in read only mode
+        *self = serde_json::from_reader(reader).unwrap();
+    }
+
+    pub fn update(&self) {
+        let file_path = Path::new("config/run_history.json");
+        let writer = File::options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(file_path)
+            .unwrap(); // open with write perms
+        serde_json::to_writer(writer, self).unwrap();
+    }
+
+    // store a finished (or abandoned) run and write the history to disk
+    pub fn archive(&mut self, run: &CurrentRunJson, deck_data: &DeckDataWrapper) {
+        if run.decks.is_empty() {
+            // the run never started, nothing to remember
+            return;
+        }
+
+        let mut decks = Vec::new();
+        for (i, deck) in run.decks.iter().enumerate() {
+            decks.push(ArchivedDeck {
+                deck: *deck,
+                name: deck_data
+                    .decks
+                    .get(*deck)
+                    .map(|data| data.name.clone())
+                    .unwrap_or_default(),
+                drawn: run.drawn.get(i).copied(),
+                completed: run.completed_decks.get(i).copied().unwrap_or(false),
+                time: run
+                    .timers
+                    .splits
+                    .get(i)
+                    .map(|split| split.time)
+                    .unwrap_or(0.0),
+            });
+        }
+
+        self.runs.push(ArchivedRun {
+            seed: run.seed,
+            started: run.started,
+            finished: unix_time(),
+            score: run.score,
+            won: run.is_finished(),
+            time: run.timers.run,
+            decks,
+        });
+        self.update();
+    }
+}
+
+impl PresetsJson {
+    // every file in config/presets is a preset, so shared presets can just be dropped in there
+    pub fn load(&mut self) {
+        self.presets.clear();
+
+        let entries = match fs::read_dir("config/presets") {
+            Ok(entries) => entries,
+            Err(_) => return, // no presets have been made yet
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|x| x.to_str()) != Some("json") {
+                continue;
+            }
+            match File::open(&path).map(serde_json::from_reader::<_, DeckPreset>) {
+                Ok(Ok(preset)) => self.presets.push(preset),
+                _ => println!("Could not load preset {}", path.display()),
+            }
+        }
+        self.presets
+            .sort_by_key(|preset| preset.name.to_lowercase());
+    }
+
+    // add the preset, replacing any preset with the same name, and write it to disk
+    pub fn save(&mut self, preset: DeckPreset) -> std::io::Result<PathBuf> {
+        // names like "A B" and "A_B" end up in the same file, dont let one overwrite the other
+        let file_name = preset.file_name();
+        if let Some(other) = self
+            .presets
+            .iter()
+            .find(|x| x.name != preset.name && x.file_name() == file_name)
+        {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("\"{}\" is already saved as {}", other.name, file_name),
+            ));
+        }
+
+        let path = preset.write("config/presets")?;
+
+        match self.presets.iter().position(|x| x.name == preset.name) {
+            Some(i) => self.presets[i] = preset,
+            None => self.presets.push(preset),
+        }
+        self.presets
+            .sort_by_key(|preset| preset.name.to_lowercase());
+        Ok(path)
+    }
+
+    // the preset that matches the enabled decks exactly, if there is one
+    pub fn active(&self, enabled_json: &EnabledJson, deck_data: &DeckDataWrapper) -> Option<usize> {
+        self.presets
+            .iter()
+            .position(|preset| preset.matches(enabled_json, deck_data))
+    }
+}
+
+impl ThemesJson {
+    // every file in themes/ is a theme, same as presets
+    pub fn load(&mut self) {
+        self.themes.clear();
+
+        let entries = match fs::read_dir("themes") {
+            Ok(entries) => entries,
+            Err(_) => return, // only the built in theme then
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|x| x.to_str()) != Some("json") {
+                continue;
+            }
+            match File::open(&path).map(serde_json::from_reader::<_, ThemeJson>) {
+                Ok(Ok(theme)) => self.themes.push(theme),
+                _ => println!("Could not load theme {}", path.display()),
+            }
+        }
+        self.themes.sort_by_key(|theme| theme.name.to_lowercase());
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ThemeJson> {
+        self.themes.iter().find(|theme| theme.name == name)
+    }
+}
+
+impl DeckPreset {
+    pub fn new(name: &str, enabled_json: &EnabledJson, deck_data: &DeckDataWrapper) -> Self {
+        DeckPreset {
+            name: name.to_owned(),
+            decks: enabled_json
+                .enabled
+                .iter()
+                .map(|deck| deck_data.decks[*deck].name.clone())
+                .collect(),
+        }
+    }
+
+    // the decks in decks.json that are in this preset, decks that dont exist anymore are skipped
+    pub fn deck_numbers(&self, deck_data: &DeckDataWrapper) -> Vec<usize> {
+        (0..deck_data.decks.len())
+            .filter(|deck| self.decks.contains(&deck_data.decks[*deck].name))
+            .collect()
+    }
+
+    pub fn matches(&self, enabled_json: &EnabledJson, deck_data: &DeckDataWrapper) -> bool {
+        let mut enabled = enabled_json.enabled.clone();
+        enabled.sort_unstable();
+        enabled == self.deck_numbers(deck_data)
+    }
+
+    // enable every deck in the preset and disable the rest
+    pub fn apply(&self, enabled_json: &mut EnabledJson, deck_data: &DeckDataWrapper) {
+        let decks = self.deck_numbers(deck_data);
+
+        for deck in 0..deck_data.decks.len() {
+            if decks.contains(&deck) {
+                if !enabled_json.check_enabled(&deck) {
+                    enabled_json.enable(deck);
+                }
+            } else if !enabled_json.check_disabled(&deck) {
+                enabled_json.disable(deck);
+            }
+        }
+    }
+
+    // presets are written to exports/ so they are easy to find and send to someone
+    pub fn export(&self) -> std::io::Result<PathBuf> {
+        self.write("exports")
+    }
+
+    // keep the name readable but safe to use as a file name
+    pub fn file_name(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| match c.is_alphanumeric() || c == '-' {
+                true => c,
+                false => '_',
+            })
+            .collect();
+        format!("preset_{}.json", name)
     }
+
+    fn write(&self, dir: &str) -> std::io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        let path = Path::new(dir).join(self.file_name());
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
 }
 
 pub struct JsonPlugin;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug)]
 pub struct CurrentRunJson {
     pub decks: Vec<usize>,
     pub completed_decks: Vec<bool>, // map of compeleted decks
     pub score: usize,
     pub hand: Vec<usize>,
+    #[serde(default)]
+    pub drawn: Vec<DrawnCards>, // which card was drawn from each deck
+    #[serde(default)]
+    pub history: ActionHistory, // undo/redo stacks
+    #[serde(default)]
+    pub seed: u64, // seed used for the first draw of every deck
+    #[serde(default)]
+    pub started: u64, // unix time the run started at
+    #[serde(default)]
+    pub timers: RunTimers,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RunTimers {
+    pub run: f64,               // seconds the run clock has been running
+    pub current: Option<usize>, // the deck that is in progress
+    pub splits: Vec<Split>,     // one per deck in the run, same order
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy)]
+pub struct Split {
+    pub time: f64,                // seconds spent on the deck
+    pub finished_at: Option<f64>, // run clock when the deck got completed
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct DrawnCards {
+    pub primary: usize,   // index into the primary cards
+    pub secondary: usize, // index into the secondary cards
+}
+
+// every change to the run goes through one of these so it can be undone
+// decks are referred to by their position in the run, except for deck selection
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunCommand {
+    EnableDeck {
+        deck: usize,
+        index: usize,
+        completed: bool,
+        drawn: Option<DrawnCards>,
+    },
+    DisableDeck {
+        deck: usize,
+        index: usize,
+        completed: bool,
+        drawn: Option<DrawnCards>,
+    },
+    CompleteDeck(usize),
+    UncompleteDeck(usize),
+    Reroll {
+        deck: usize,
+        primary: bool,
+        from: usize,
+        to: usize,
+    },
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ActionHistory {
+    pub undo: Vec<RunCommand>,
+    pub redo: Vec<RunCommand>,
+}
+
+// a named set of enabled decks
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeckPreset {
+    pub name: String,
+    pub decks: Vec<String>, // stored by name so presets still work when shared or when decks.json changes
+}
+
+#[derive(Default, Debug)]
+pub struct PresetsJson {
+    pub presets: Vec<DeckPreset>,
+}
+
+// a theme file, anything left out falls back to the built in theme
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ThemeJson {
+    pub name: String,
+    pub font: Option<String>, // relative to the assets folder
+    pub title_size: Option<f32>,
+    pub button_font_size: Option<f32>,
+    pub text_size: Option<f32>,
+    pub button_size: Option<[f32; 2]>, // width and height
+    pub text_color: Option<String>,
+    pub background_color: Option<String>,
+    pub background_image: Option<String>, // relative to the assets folder
+    pub colors: ThemeColorsJson,
+}
+
+// colours are hex strings like "#59BF59"
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ThemeColorsJson {
+    pub normal_button: Option<String>,
+    pub hovered_button: Option<String>,
+    pub pressed_button: Option<String>,
+    pub disabled_button: Option<String>,
+    pub disabled_deck: Option<String>,
+    pub enabled_deck: Option<String>,
+    pub in_progress_deck: Option<String>,
+    pub locked_deck: Option<String>,
+}
+
+#[derive(Default, Debug)]
+pub struct ThemesJson {
+    pub themes: Vec<ThemeJson>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct RunHistoryJson {
+    pub runs: Vec<ArchivedRun>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArchivedRun {
+    pub seed: u64,
+    pub started: u64,  // unix time
+    pub finished: u64, // unix time
+    pub score: usize,
+    pub won: bool, // every deck was completed
+    #[serde(default)]
+    pub time: f64, // run clock in seconds
+    pub decks: Vec<ArchivedDeck>,
+}
+
+impl ArchivedRun {
+    // how long the run took in seconds, runs from before the run clock use the wall clock
+    pub fn length(&self) -> u64 {
+        if self.time > 0.0 {
+            return self.time as u64;
+        }
+        self.finished.saturating_sub(self.started)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArchivedDeck {
+    pub deck: usize,  // index into decks.json
+    pub name: String, // in case decks.json changes later
+    pub drawn: Option<DrawnCards>,
+    pub completed: bool,
+    #[serde(default)]
+    pub time: f64, // seconds spent on the deck
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct EnabledJson {
     pub disabled: Vec<usize>,
     pub enabled: Vec<usize>,
+    #[serde(skip)]
+    pub saved: Vec<usize>, // the enabled decks as they were last loaded or saved, sorted
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -143,9 +774,360 @@ pub struct DeckDataWrapper {
 pub struct DeckData {
     pub primary_cards: usize, // card count
 
-    pub name: String,      // name used in the deck selection
-    pub file: String,      // path to sprite sheet
+    pub name: String, // name used in the deck selection
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub names: HashMap<String, String>, // translated names by language, e.g. "de": "..."
+    pub file: String, // path to sprite sheet
     pub back_file: String, // path to the back image
 
     pub secondary_cards: usize,
+
+    #[serde(default)]
+    pub tags: Vec<String>, // free form, e.g. roguelike, platformer
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub estimated_minutes: Option<u32>, // rough time to finish one card
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    #[serde(default)]
+    pub platforms: Vec<String>,
+    #[serde(default)]
+    pub owned: Option<bool>, // None if it hasnt been filled in
+
+    #[serde(default)]
+    pub primary_text: Vec<String>, // text of every primary card, optional
+    #[serde(default)]
+    pub secondary_text: Vec<String>, // text of every secondary card, optional
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+impl DeckData {
+    pub fn length_label(&self) -> Option<&'static str> {
+        match self.estimated_minutes? {
+            0..=29 => Some("short"),
+            30..=89 => Some("medium"),
+            _ => Some("long"),
+        }
+    }
+
+    // the free form tags plus one tag for every category that is filled in
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags = self.tags.clone();
+
+        if let Some(genre) = &self.genre {
+            tags.push(genre.clone());
+        }
+        if let Some(length) = self.length_label() {
+            tags.push(format!("{} length", length));
+        }
+        if let Some(difficulty) = &self.difficulty {
+            tags.push(difficulty.label().to_owned());
+        }
+        for platform in self.platforms.iter() {
+            tags.push(platform.clone());
+        }
+        match self.owned {
+            Some(true) => tags.push(String::from("owned")),
+            Some(false) => tags.push(String::from("not owned")),
+            None => {}
+        }
+
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    // the name in the given language, the normal name if it hasnt been translated
+    pub fn display_name(&self, language: &str) -> &str {
+        self.names.get(language).unwrap_or(&self.name)
+    }
+
+    // one line per category, for showing the deck on hover
+    pub fn describe(&self, locale: &Locale) -> String {
+        let mut lines = vec![self.display_name(&locale.language).to_owned()];
+
+        if let Some(genre) = &self.genre {
+            lines.push(locale.format("deck.genre", &[genre]));
+        }
+        if let (Some(minutes), Some(length)) = (self.estimated_minutes, self.length_label()) {
+            let length = locale.get(&format!("deck.length_{}", length));
+            lines.push(locale.format("deck.length", &[&length, &minutes]));
+        }
+        if let Some(difficulty) = &self.difficulty {
+            let difficulty = locale.get(&format!("deck.difficulty_{}", difficulty.label()));
+            lines.push(locale.format("deck.difficulty", &[&difficulty]));
+        }
+        if !self.platforms.is_empty() {
+            lines.push(locale.format("deck.platforms", &[&self.platforms.join(", ")]));
+        }
+        match self.owned {
+            Some(true) => lines.push(locale.get("deck.owned")),
+            Some(false) => lines.push(locale.get("deck.not_owned")),
+            None => {}
+        }
+        if !self.tags.is_empty() {
+            lines.push(locale.format("deck.tags", &[&self.tags.join(", ")]));
+        }
+        lines.join("\n")
+    }
+
+    pub fn card_text(&self, primary: bool, index: usize) -> Option<&str> {
+        let text = match primary {
+            true => &self.primary_text,
+            false => &self.secondary_text,
+        };
+        text.get(index).map(|text| text.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deck_data() -> DeckDataWrapper {
+        serde_json::from_str(
+            r#"{"decks": [
+                {"name": "A", "file": "a.png", "back_file": "a_back.png", "primary_cards": 10, "secondary_cards": 5},
+                {"name": "B", "file": "b.png", "back_file": "b_back.png", "primary_cards": 20, "secondary_cards": 8},
+                {"name": "C", "file": "c.png", "back_file": "c_back.png", "primary_cards": 3, "secondary_cards": 1},
+                {"name": "D", "file": "d.png", "back_file": "d_back.png", "primary_cards": 12, "secondary_cards": 12}
+            ]}"#,
+        )
+        .unwrap()
+    }
+
+    fn run_with(decks: &[usize], seed: u64) -> CurrentRunJson {
+        let mut run = CurrentRunJson {
+            decks: decks.to_vec(),
+            seed,
+            ..Default::default()
+        };
+        run.prepare(&deck_data());
+        run
+    }
+
+    // everything a command can change
+    fn state(run: &CurrentRunJson) -> (Vec<usize>, Vec<bool>, Vec<DrawnCards>, usize) {
+        (
+            run.decks.clone(),
+            run.completed_decks.clone(),
+            run.drawn.clone(),
+            run.score,
+        )
+    }
+
+    #[test]
+    fn inverse_of_inverse_is_the_command() {
+        let drawn = Some(DrawnCards {
+            primary: 1,
+            secondary: 2,
+        });
+        let commands = [
+            RunCommand::EnableDeck {
+                deck: 3,
+                index: 1,
+                completed: false,
+                drawn: None,
+            },
+            RunCommand::DisableDeck {
+                deck: 0,
+                index: 0,
+                completed: true,
+                drawn,
+            },
+            RunCommand::CompleteDeck(2),
+            RunCommand::UncompleteDeck(2),
+            RunCommand::Reroll {
+                deck: 1,
+                primary: false,
+                from: 4,
+                to: 7,
+            },
+        ];
+
+        for command in commands {
+            assert_ne!(command.inverse(), command);
+            assert_eq!(command.inverse().inverse(), command);
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let mut run = run_with(&[0, 1, 2], 42);
+        let before = state(&run);
+
+        let from = run.drawn[1].primary;
+        let commands = [
+            RunCommand::CompleteDeck(0),
+            RunCommand::Reroll {
+                deck: 1,
+                primary: true,
+                from,
+                to: (from + 1) % 20,
+            },
+            run.toggle_deck_command(3),
+            RunCommand::CompleteDeck(3),
+            RunCommand::UncompleteDeck(0),
+        ];
+        for command in commands {
+            run.apply(command);
+        }
+        let after = state(&run);
+        assert_eq!(run.decks, vec![0, 1, 2, 3]);
+        assert_eq!(run.completed_decks, vec![false, false, false, true]);
+        assert_eq!(run.score, 1);
+
+        while run.undo(true) {}
+        assert_eq!(state(&run), before);
+        assert!(run.history.undo.is_empty());
+
+        while run.redo(true) {}
+        assert_eq!(state(&run), after);
+        assert!(run.history.redo.is_empty());
+    }
+
+    #[test]
+    fn removing_a_deck_is_undone_in_place() {
+        let mut run = run_with(&[0, 1, 2], 7);
+        run.apply(RunCommand::CompleteDeck(1));
+        let before = state(&run);
+
+        let command = run.toggle_deck_command(1);
+        run.apply(command);
+        assert_eq!(run.decks, vec![0, 2]);
+        assert_eq!(run.completed_decks, vec![false, false]);
+
+        assert!(run.undo(true));
+        assert_eq!(state(&run), before);
+    }
+
+    #[test]
+    fn selection_is_only_undone_when_allowed() {
+        let mut run = run_with(&[0], 1);
+        let command = run.toggle_deck_command(2);
+        run.apply(command);
+
+        assert!(!run.undo(false));
+        assert_eq!(run.decks, vec![0, 2]);
+        assert!(run.undo(true));
+        assert_eq!(run.decks, vec![0]);
+
+        assert!(!run.redo(false));
+        assert!(run.redo(true));
+        assert_eq!(run.decks, vec![0, 2]);
+    }
+
+    #[test]
+    fn a_new_command_clears_redo() {
+        let mut run = run_with(&[0, 1], 3);
+        run.apply(RunCommand::CompleteDeck(0));
+        assert!(run.undo(false));
+        assert_eq!(run.history.redo.len(), 1);
+
+        run.apply(RunCommand::CompleteDeck(1));
+        assert!(run.history.redo.is_empty());
+        assert!(!run.redo(true));
+        assert_eq!(run.completed_decks, vec![false, true]);
+    }
+
+    #[test]
+    fn prepare_draws_the_same_cards_for_a_seed() {
+        let first = run_with(&[0, 1, 2, 3], 1234);
+        let second = run_with(&[0, 1, 2, 3], 1234);
+        assert_eq!(first.drawn, second.drawn);
+
+        // every deck draws from its own rng, so the order the decks were picked in doesnt matter
+        let reversed = run_with(&[3, 2, 1, 0], 1234);
+        let mut drawn = reversed.drawn.clone();
+        drawn.reverse();
+        assert_eq!(first.drawn, drawn);
+
+        let data = deck_data();
+        for (deck, drawn) in first.decks.iter().zip(first.drawn.iter()) {
+            assert!(drawn.primary < data.decks[*deck].primary_cards);
+            assert!(drawn.secondary < data.decks[*deck].secondary_cards);
+        }
+    }
+
+    #[test]
+    fn presets_sharing_a_file_name_dont_overwrite_each_other() {
+        let preset = |name: &str| DeckPreset {
+            name: name.to_owned(),
+            decks: Vec::new(),
+        };
+        let mut presets = PresetsJson {
+            presets: vec![preset("Short games")],
+        };
+
+        assert_eq!(
+            preset("Short games").file_name(),
+            preset("Short_games").file_name()
+        );
+        assert!(presets.save(preset("Short_games")).is_err());
+        assert_eq!(presets.presets.len(), 1);
+        assert_eq!(presets.presets[0].name, "Short games");
+    }
+
+    #[test]
+    fn prepare_keeps_existing_draws() {
+        let data = deck_data();
+        let mut run = run_with(&[0, 1], 99);
+        run.drawn[0] = DrawnCards {
+            primary: 9,
+            secondary: 4,
+        };
+
+        run.decks.push(2);
+        run.prepare(&data);
+
+        assert_eq!(run.seed, 99);
+        assert_eq!(run.drawn.len(), 3);
+        assert_eq!(
+            run.drawn[0],
+            DrawnCards {
+                primary: 9,
+                secondary: 4
+            }
+        );
+        assert_eq!(run.drawn[2], run_with(&[2], 99).drawn[0]);
+        assert_eq!(run.completed_decks.len(), 3);
+        assert_eq!(run.timers.splits.len(), 3);
+    }
+
+    #[test]
+    fn enabling_and_disabling_a_deck_again_is_saved() {
+        let mut enabled_json = EnabledJson {
+            enabled: vec![2, 0],
+            disabled: vec![1],
+            saved: vec![0, 2],
+        };
+        assert!(enabled_json.is_saved());
+
+        enabled_json.enable(1);
+        assert!(!enabled_json.is_saved());
+
+        // back to the same decks, just in a different order
+        enabled_json.disable(1);
+        enabled_json.disable(0);
+        enabled_json.enable(0);
+        assert!(enabled_json.is_saved());
+    }
 }
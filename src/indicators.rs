@@ -0,0 +1,331 @@
+use bevy::prelude::*;
+
+use crate::{
+    constants::{CARD_H, CARD_W},
+    deck::BoardCard,
+    handle_json::{CurrentRunJson, EnabledJson},
+    localization::Locale,
+    randomize::DeckLocks,
+    states_and_ui::{DeckNumber, GameState},
+    theme::Theme,
+};
+
+pub struct IndicatorPlugin;
+
+impl Plugin for IndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(IndicatorSettings { enabled: true })
+            .add_system(add_indicators)
+            .add_system_set(
+                SystemSet::on_update(GameState::DeckSelection)
+                    .with_system(update_deck_selection_indicators),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::PreGame).with_system(update_pre_game_indicators),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame).with_system(update_board_indicators),
+            );
+    }
+}
+
+// stored in the [Accessibility] section of settings.ini
+pub struct IndicatorSettings {
+    pub enabled: bool,
+}
+
+// what a deck is doing, shown with an icon and a word so it doesnt only rely on the tint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeckState {
+    Enabled,
+    Disabled,
+    Selected,
+    Locked,
+    InProgress,
+    Completed,
+}
+
+impl DeckState {
+    fn icon(self) -> &'static str {
+        match self {
+            DeckState::Enabled | DeckState::Selected | DeckState::Completed => "ui/icon_check.png",
+            DeckState::Disabled => "ui/icon_cross.png",
+            DeckState::Locked => "ui/icon_lock.png",
+            DeckState::InProgress => "ui/icon_clock.png",
+        }
+    }
+
+    // the key of its name in the language files
+    pub fn key(self) -> &'static str {
+        match self {
+            DeckState::Enabled => "deck_state.enabled",
+            DeckState::Disabled => "deck_state.disabled",
+            DeckState::Selected => "deck_state.selected",
+            DeckState::Locked => "deck_state.locked",
+            DeckState::InProgress => "deck_state.in_progress",
+            DeckState::Completed => "deck_state.completed",
+        }
+    }
+}
+
+// the pieces of the indicator put on a deck
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum IndicatorPart {
+    Icon,
+    Background,
+    Label,
+}
+
+const ICON_SIZE: f32 = 64.0;
+const LABEL_SIZE: f32 = 24.0;
+const LABEL_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.7);
+
+// every deck back and the primary card of every deck on the board gets an indicator, hidden until it has a state
+fn add_indicators(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    query: Query<(Entity, Option<&BoardCard>), Added<DeckNumber>>,
+) {
+    let label_style = TextStyle {
+        font: theme.font.clone(),
+        font_size: LABEL_SIZE,
+        color: theme.text_color,
+    };
+    let hidden = Visibility { is_visible: false };
+
+    for (entity, card) in query.iter() {
+        match card {
+            Some(card) if !card.primary => {}
+            Some(_) => {
+                // the card is CARD_H wide and CARD_W tall, before the board scales it
+                let top = CARD_W / 2.0;
+                commands.entity(entity).with_children(|parent| {
+                    parent
+                        .spawn_bundle(SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::splat(ICON_SIZE * 1.5)),
+                                ..Default::default()
+                            },
+                            transform: Transform::from_xyz(
+                                CARD_H / 2.0 - ICON_SIZE,
+                                top - ICON_SIZE,
+                                0.2,
+                            ),
+                            visibility: hidden.clone(),
+                            ..Default::default()
+                        })
+                        .insert(IndicatorPart::Icon);
+                    parent
+                        .spawn_bundle(SpriteBundle {
+                            sprite: Sprite {
+                                color: LABEL_BACKGROUND,
+                                custom_size: Some(Vec2::new(CARD_H, LABEL_SIZE * 2.5)),
+                                ..Default::default()
+                            },
+                            transform: Transform::from_xyz(0.0, -top + LABEL_SIZE * 1.25, 0.2),
+                            visibility: hidden.clone(),
+                            ..Default::default()
+                        })
+                        .insert(IndicatorPart::Background);
+                    parent
+                        .spawn_bundle(Text2dBundle {
+                            text: Text::with_section(
+                                "",
+                                TextStyle {
+                                    font_size: LABEL_SIZE * 1.5,
+                                    ..label_style.clone()
+                                },
+                                TextAlignment {
+                                    vertical: VerticalAlign::Center,
+                                    horizontal: HorizontalAlign::Center,
+                                },
+                            ),
+                            transform: Transform::from_xyz(0.0, -top + LABEL_SIZE * 1.25, 0.3),
+                            visibility: hidden.clone(),
+                            ..Default::default()
+                        })
+                        .insert(IndicatorPart::Label);
+                });
+            }
+            None => {
+                commands.entity(entity).with_children(|parent| {
+                    parent
+                        .spawn_bundle(ImageBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(ICON_SIZE), Val::Px(ICON_SIZE)),
+                                position_type: PositionType::Absolute,
+                                position: Rect {
+                                    top: Val::Px(-ICON_SIZE / 4.0),
+                                    right: Val::Px(-ICON_SIZE / 4.0),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            },
+                            visibility: hidden.clone(),
+                            ..Default::default()
+                        })
+                        .insert(IndicatorPart::Icon);
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Px(LABEL_SIZE * 1.5)),
+                                position_type: PositionType::Absolute,
+                                position: Rect {
+                                    bottom: Val::Px(0.0),
+                                    left: Val::Px(0.0),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            },
+                            color: LABEL_BACKGROUND.into(),
+                            visibility: hidden.clone(),
+                            ..Default::default()
+                        })
+                        .insert(IndicatorPart::Background);
+                    parent
+                        .spawn_bundle(TextBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                position: Rect {
+                                    bottom: Val::Px(LABEL_SIZE * 0.25),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            },
+                            text: Text::with_section(
+                                "",
+                                label_style.clone(),
+                                TextAlignment {
+                                    vertical: VerticalAlign::Center,
+                                    horizontal: HorizontalAlign::Center,
+                                },
+                            ),
+                            visibility: hidden.clone(),
+                            ..Default::default()
+                        })
+                        .insert(IndicatorPart::Label);
+                });
+            }
+        }
+    }
+}
+
+type IndicatorQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static IndicatorPart,
+        &'static mut Visibility,
+        Option<&'static mut UiImage>,
+        Option<&'static mut Handle<Image>>,
+        Option<&'static mut Text>,
+    ),
+>;
+
+// point a deck's indicator at its state, or hide it when there is nothing to show
+fn show_state(
+    state: Option<DeckState>,
+    children: &Children,
+    locale: &Locale,
+    asset_server: &AssetServer,
+    parts: &mut IndicatorQuery,
+) {
+    for child in children.iter() {
+        let (part, mut visibility, image, sprite, text) = match parts.get_mut(*child) {
+            Ok(part) => part,
+            Err(_) => continue,
+        };
+
+        if visibility.is_visible != state.is_some() {
+            visibility.is_visible = state.is_some();
+        }
+        let state = match state {
+            Some(state) => state,
+            None => continue,
+        };
+
+        match (part, image, sprite, text) {
+            (IndicatorPart::Icon, Some(mut image), _, _) => {
+                let icon = asset_server.load(state.icon());
+                if image.0 != icon {
+                    image.0 = icon;
+                }
+            }
+            (IndicatorPart::Icon, _, Some(mut sprite), _) => {
+                let icon = asset_server.load(state.icon());
+                if *sprite != icon {
+                    *sprite = icon;
+                }
+            }
+            (IndicatorPart::Label, _, _, Some(mut text)) => {
+                let label = locale.get(state.key());
+                if text.sections[0].value != label {
+                    text.sections[0].value = label;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_deck_selection_indicators(
+    settings: Res<IndicatorSettings>,
+    enabled_json: Res<EnabledJson>,
+    locale: Res<Locale>,
+    asset_server: Res<AssetServer>,
+    query: Query<(&DeckNumber, &Children), (With<Button>, Without<IndicatorPart>)>,
+    mut parts: IndicatorQuery,
+) {
+    for (deck_num, children) in query.iter() {
+        let state = match enabled_json.check_disabled(&deck_num.num) {
+            true => DeckState::Disabled,
+            false => DeckState::Enabled,
+        };
+        let state = Some(state).filter(|_| settings.enabled);
+        show_state(state, children, &locale, &asset_server, &mut parts);
+    }
+}
+
+fn update_pre_game_indicators(
+    settings: Res<IndicatorSettings>,
+    current_run_json: Res<CurrentRunJson>,
+    locks: Res<DeckLocks>,
+    locale: Res<Locale>,
+    asset_server: Res<AssetServer>,
+    query: Query<(&DeckNumber, &Children), (With<Button>, Without<IndicatorPart>)>,
+    mut parts: IndicatorQuery,
+) {
+    for (deck_num, children) in query.iter() {
+        let state = if !settings.enabled || !current_run_json.check_deck(&deck_num.num) {
+            None
+        } else if locks.decks.contains(&deck_num.num) {
+            Some(DeckState::Locked)
+        } else {
+            Some(DeckState::Selected)
+        };
+        show_state(state, children, &locale, &asset_server, &mut parts);
+    }
+}
+
+fn update_board_indicators(
+    settings: Res<IndicatorSettings>,
+    current_run_json: Res<CurrentRunJson>,
+    locale: Res<Locale>,
+    asset_server: Res<AssetServer>,
+    query: Query<(&DeckNumber, &Visibility, &Children), (With<BoardCard>, Without<IndicatorPart>)>,
+    mut parts: IndicatorQuery,
+) {
+    for (deck_num, visibility, children) in query.iter() {
+        // nothing is shown on a card that is still face down
+        let state = if !settings.enabled || !visibility.is_visible {
+            None
+        } else if current_run_json.completed_decks[deck_num.num] {
+            Some(DeckState::Completed)
+        } else if current_run_json.timers.current == Some(deck_num.num) {
+            Some(DeckState::InProgress)
+        } else {
+            None
+        };
+        show_state(state, children, &locale, &asset_server, &mut parts);
+    }
+}
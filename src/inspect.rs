@@ -0,0 +1,239 @@
+use bevy::{prelude::*, text::Text2dBounds};
+
+use crate::{
+    actual_game::{card_under_cursor, cursor_to_world},
+    constants::{CARD_H, CARD_W},
+    deck::{BoardCard, Decks},
+    handle_json::{CurrentRunJson, DeckDataWrapper},
+    localization::Locale,
+    states_and_ui::{DeckNumber, DialogData, GameState},
+    theme::Theme,
+    MainCamera,
+};
+
+pub struct InspectPlugin;
+
+impl Plugin for InspectPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Inspected::default())
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    // after the board handled the click, so the click that closes the view doesnt also hit a card
+                    .with_system(pick_inspected_card.after("ingame_input"))
+                    .with_system(update_inspect_view),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(close_inspect_view));
+    }
+}
+
+const HOVER_DELAY: f32 = 0.8; // seconds a card has to be hovered before it gets inspected
+
+// the card shown in the inspect view, if any
+#[derive(Default, Debug)]
+pub struct Inspected {
+    pub card: Option<(usize, bool)>, // deck and whether its the primary card
+    pub pinned: bool,                // opened with a middle click, stays open until closed
+}
+
+#[derive(Component)]
+struct InspectView {
+    // the root of the inspect view, everything in it is a child
+}
+
+// middle click a card to pin it open, or hover over it for a bit
+fn pick_inspected_card(
+    mut inspected: ResMut<Inspected>,
+    mut hover: Local<(Option<(usize, bool)>, f32)>, // the card under the cursor and for how long
+    time: Res<Time>,
+    mouse: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    dialog_data: Res<DialogData>,
+    windows: Res<Windows>,
+    camera_query: Query<&Transform, With<MainCamera>>,
+    ui_query: Query<&Interaction, With<Button>>,
+    card_query: Query<(&Transform, &DeckNumber, &BoardCard)>,
+) {
+    if dialog_data.is_open() {
+        return;
+    }
+
+    if inspected.pinned {
+        if keys.just_pressed(KeyCode::Escape)
+            || mouse.just_released(MouseButton::Left)
+            || mouse.just_pressed(MouseButton::Middle)
+        {
+            *inspected = Inspected::default();
+            *hover = (None, 0.0);
+        }
+        return;
+    }
+
+    let over_ui = ui_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+
+    let card = match (over_ui, cursor_to_world(&windows, camera_query.single())) {
+        (false, Some(cursor)) => card_under_cursor(cursor, card_query.iter()),
+        _ => None,
+    };
+
+    if card != hover.0 {
+        *hover = (card, 0.0);
+    }
+    hover.1 += time.delta_seconds();
+
+    if mouse.just_pressed(MouseButton::Middle) && card.is_some() {
+        inspected.card = card;
+        inspected.pinned = true;
+    } else {
+        let shown = match hover.1 >= HOVER_DELAY {
+            true => card,
+            false => None,
+        };
+        if inspected.card != shown {
+            inspected.card = shown;
+        }
+    }
+}
+
+// rebuild the view when the inspected card changes, and keep it in front of the camera
+fn update_inspect_view(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    inspected: Res<Inspected>,
+    current_run_json: Res<CurrentRunJson>,
+    deck_data: Res<DeckDataWrapper>,
+    decks: Res<Decks>,
+    windows: Res<Windows>,
+    camera_query: Query<&Transform, (With<MainCamera>, Without<InspectView>)>,
+    mut view_query: Query<(Entity, &mut Transform), With<InspectView>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let camera = camera_query.single();
+
+    let card_size = Vec2::new(CARD_H, CARD_W); // CARD_H is the width, CARD_W is the height
+    let text_height = 260.0;
+    let padding = 20.0;
+    let size = Vec2::new(card_size.x, card_size.y + text_height) + padding * 2.0;
+
+    // full size unless the screen is too small, on the side of the screen the cursor isnt on
+    let fit = ((window.height() - 40.0) / size.y).min(1.0);
+    let side = match window.cursor_position() {
+        Some(cursor) if cursor.x > window.width() / 2.0 => -1.0,
+        _ => 1.0,
+    };
+    let offset = Vec2::new(
+        side * (window.width() - size.x * fit) / 2.0 - side * 20.0,
+        0.0,
+    );
+
+    let follow = Transform {
+        translation: (camera.translation.truncate() + offset * camera.scale.truncate())
+            .extend(500.0),
+        scale: camera.scale * fit,
+        ..Default::default()
+    };
+
+    if !inspected.is_changed() {
+        for (_, mut transform) in view_query.iter_mut() {
+            *transform = follow;
+        }
+        return;
+    }
+
+    for (entity, _) in view_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let (deck, primary) = match inspected.card {
+        Some(card) => card,
+        None => return,
+    };
+    let index = match current_run_json.drawn.get(deck) {
+        Some(drawn) if primary => drawn.primary,
+        Some(drawn) => drawn.secondary,
+        None => return,
+    };
+    let together = &decks.0[deck];
+    let sheet = match primary {
+        true => &together.primary,
+        false => &together.secondary,
+    };
+    let data = &deck_data.decks[current_run_json.decks[deck]];
+
+    let key = match primary {
+        true => "inspect.primary",
+        false => "inspect.secondary",
+    };
+    let mut text = locale.format(key, &[&data.display_name(&locale.language), &(index + 1)]);
+    if let Some(card_text) = data.card_text(primary, index) {
+        text += &format!("\n{}", card_text);
+    }
+    if current_run_json
+        .completed_decks
+        .get(deck)
+        .copied()
+        .unwrap_or(false)
+    {
+        text += &format!("\n{}", locale.get("inspect.completed"));
+    } else if current_run_json.timers.current == Some(deck) {
+        text += &format!("\n{}", locale.get("inspect.in_progress"));
+    }
+    if inspected.pinned {
+        text += &format!("\n\n{}", locale.get("inspect.close"));
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.1, 0.1, 0.1, 0.9),
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            transform: follow,
+            ..Default::default()
+        })
+        .insert(InspectView {})
+        .with_children(|parent| {
+            parent.spawn_bundle(SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(index + sheet.offset),
+                texture_atlas: sheet.sheet.clone(),
+                transform: Transform::from_xyz(0.0, (size.y - card_size.y) / 2.0 - padding, 1.0),
+                ..Default::default()
+            });
+            parent.spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    text,
+                    TextStyle {
+                        font: theme.font.clone(),
+                        font_size: theme.text_size,
+                        color: theme.text_color,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Top,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                text_2d_bounds: Text2dBounds {
+                    size: Size::new(card_size.x, text_height),
+                },
+                transform: Transform::from_xyz(0.0, -size.y / 2.0 + padding + text_height, 1.0),
+                ..Default::default()
+            });
+        });
+}
+
+fn close_inspect_view(
+    mut commands: Commands,
+    mut inspected: ResMut<Inspected>,
+    query: Query<Entity, With<InspectView>>,
+) {
+    *inspected = Inspected::default();
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fmt::Display, fs, path::Path};
+
+use bevy::prelude::*;
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Locale::default());
+    }
+}
+
+const LANG_DIR: &str = "lang";
+const FALLBACK_LANGUAGE: &str = "en";
+
+// every string the menus show, from lang/<language>.lang
+#[derive(Default, Debug)]
+pub struct Locale {
+    pub language: String, // the file name, e.g. "en"
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>, // english, for anything a translation doesnt have yet
+}
+
+// "key = value" lines, # starts a comment and \n in a value is a line break
+fn parse_lang_file(path: &Path) -> HashMap<String, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(_) => {
+            println!("Could not load language file {}", path.display());
+            return HashMap::new();
+        }
+    };
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_owned(), value.trim().replace("\\n", "\n")))
+        .collect()
+}
+
+fn lang_path(language: &str) -> std::path::PathBuf {
+    Path::new(LANG_DIR).join(format!("{}.lang", language))
+}
+
+impl Locale {
+    pub fn load(&mut self, language: &str) {
+        self.fallback = parse_lang_file(&lang_path(FALLBACK_LANGUAGE));
+        self.strings = match language == FALLBACK_LANGUAGE {
+            true => HashMap::new(),
+            false => parse_lang_file(&lang_path(language)),
+        };
+        self.language = language.to_owned();
+    }
+
+    // the string for a key, the key itself shows up if nobody has written it yet
+    pub fn get(&self, key: &str) -> String {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_owned())
+    }
+
+    // fills in every {} in the string with the next argument
+    pub fn format(&self, key: &str, args: &[&dyn Display]) -> String {
+        let mut args = args.iter();
+        self.get(key)
+            .split("{}")
+            .enumerate()
+            .fold(String::new(), |mut text, (i, part)| {
+                if i > 0 {
+                    match args.next() {
+                        Some(arg) => text += &arg.to_string(),
+                        None => text += "{}",
+                    }
+                }
+                text + part
+            })
+    }
+
+    // the language codes there are files for, english first
+    pub fn languages() -> Vec<String> {
+        let mut languages: Vec<String> = match fs::read_dir(LANG_DIR) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().and_then(|x| x.to_str()) == Some("lang"))
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_owned()))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        languages.sort_by_key(|language| (language != FALLBACK_LANGUAGE, language.clone()));
+        languages
+    }
+}
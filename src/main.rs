@@ -1,15 +1,39 @@
 #![allow(clippy::redundant_field_names)]
 use actual_game::Score;
+use animation::{AnimationSettings, CardAnimationPlugin};
+use announce::{AnnouncePlugin, AnnounceSettings};
 use bevy::{prelude::*, window::WindowMode};
 use bevy_debug_text_overlay::OverlayPlugin;
+use board_camera::BoardCameraPlugin;
+use bulk_select::BulkSelectPlugin;
 use button_input::ButtonInputPlugin;
 use debug::DebugPlugin;
-use settings::{setup_submenu, Colors, LayoutSettings, Settings, SettingsPlugin};
+use deck_filter::DeckFilterPlugin;
+use deck_info::DeckInfoPlugin;
+use export::ExportPlugin;
+use fonts::{UiFont, UiFontPlugin};
+use indicators::{IndicatorPlugin, IndicatorSettings};
+use inspect::InspectPlugin;
+use localization::{Locale, LocalePlugin};
+use overlay_server::OverlayServerPlugin;
+use presets::PresetPlugin;
+use randomize::RandomizePlugin;
+use remote::RemotePlugin;
+use run_actions::RunActionPlugin;
+use run_events::RunEventPlugin;
+use scroll::ScrollPlugin;
+use settings::{LayoutSettings, Settings, SettingsPlugin};
+use sound::{SoundPlugin, SoundSettings};
+use stats::StatsPlugin;
 use std::collections::HashMap; // color constants for get_color()
+use theme::{Theme, ThemePlugin};
+use timer::TimerPlugin;
 
 pub mod deck;
 use deck::*;
 pub mod debug;
+pub mod deck_filter;
+pub mod deck_info;
 pub mod states_and_ui;
 use states_and_ui::*;
 pub mod handle_json;
@@ -18,7 +42,28 @@ pub mod button_input;
 pub mod constants;
 use constants::*;
 pub mod actual_game;
+pub mod animation;
+pub mod announce;
+pub mod board_camera;
+pub mod bulk_select;
+pub mod export;
+pub mod fonts;
+pub mod http;
+pub mod indicators;
+pub mod inspect;
+pub mod localization;
+pub mod overlay_server;
+pub mod presets;
+pub mod randomize;
+pub mod remote;
+pub mod run_actions;
+pub mod run_events;
+pub mod scroll;
 pub mod settings;
+pub mod sound;
+pub mod stats;
+pub mod theme;
+pub mod timer;
 
 pub struct SpriteSheetIds {
     pub ids: HashMap<String, Handle<TextureAtlas>>,
@@ -50,29 +95,67 @@ fn main() {
         .add_plugin(DeckPlugin)
         .add_plugin(MenuPlugin)
         .add_plugin(JsonPlugin)
+        .add_plugin(LocalePlugin)
         .add_plugin(StaticMut)
         .add_plugin(SettingsPlugin)
+        .add_plugin(ThemePlugin)
+        .add_plugin(UiFontPlugin)
         .add_plugin(ButtonInputPlugin)
-        .add_system(setup_submenu)
+        .add_plugin(ScrollPlugin)
+        .add_plugin(StatsPlugin)
+        .add_plugin(DeckFilterPlugin)
+        .add_plugin(DeckInfoPlugin)
+        .add_plugin(RandomizePlugin)
+        .add_plugin(PresetPlugin)
+        .add_plugin(BulkSelectPlugin)
+        .add_plugin(TimerPlugin)
+        .add_plugin(ExportPlugin)
+        .add_plugin(InspectPlugin)
+        .add_plugin(BoardCameraPlugin)
+        .add_plugin(CardAnimationPlugin)
+        .add_plugin(SoundPlugin)
+        .add_plugin(IndicatorPlugin)
+        .add_plugin(AnnouncePlugin)
+        .add_plugin(OverlayServerPlugin)
+        .add_plugin(RunEventPlugin)
+        .add_plugin(RunActionPlugin)
+        .add_plugin(RemotePlugin)
         .add_startup_system(spawn_camera)
         .add_startup_system(setup_game)
         .run();
 }
 
+// the camera looking at the in-game board
+#[derive(Component)]
+pub struct MainCamera;
+
 fn spawn_camera(mut commands: Commands) {
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(MainCamera);
     commands.spawn_bundle(UiCameraBundle::default());
 }
 
 fn setup_game(
     mut enabled_json: ResMut<EnabledJson>,
     mut deck_data: ResMut<DeckDataWrapper>,
+    mut run_history: ResMut<RunHistoryJson>,
     mut settings: ResMut<Settings>,
     mut layout: ResMut<LayoutSettings>,
-    mut colors: ResMut<Colors>,
+    mut theme: ResMut<Theme>,
+    mut themes: ResMut<ThemesJson>,
+    mut ui_font: ResMut<UiFont>,
+    asset_server: Res<AssetServer>,
+    mut globals: ResMut<GameGlobals>,
+    mut animations: ResMut<AnimationSettings>,
+    mut sound_settings: ResMut<SoundSettings>,
+    mut locale: ResMut<Locale>,
+    mut indicators: ResMut<IndicatorSettings>,
+    mut announce_settings: ResMut<AnnounceSettings>,
 ) {
     enabled_json.load();
     deck_data.load();
+    run_history.load();
     settings.load();
 
     let vertical = settings
@@ -83,13 +166,33 @@ fn setup_game(
 
     layout.vertical = vertical;
 
-    let default_button = settings.settings.get("Colors", "default_button").unwrap();
+    if let Ok(Some(decks_per_game)) = settings.settings.getuint("Game", "decks_per_game") {
+        globals.decks_per_game = decks_per_game as usize;
+    }
 
-    colors.normal_button = get_color(&default_button).unwrap();
+    if let Ok(Some(enabled)) = settings.settings.getbool("Animations", "enabled") {
+        animations.enabled = enabled;
+    }
 
-    let hovered_button = settings.settings.get("Colors", "hovered_button").unwrap();
+    if let Ok(Some(enabled)) = settings
+        .settings
+        .getbool("Accessibility", "deck_indicators")
+    {
+        indicators.enabled = enabled;
+    }
+    announce_settings.load(&settings);
 
-    colors.hovered_button = get_color(&hovered_button).unwrap();
+    sound_settings.load(&settings);
+
+    let language = settings
+        .settings
+        .get("Language", "language")
+        .unwrap_or_else(|| "en".to_owned());
+    locale.load(&language);
+
+    themes.load();
+    *theme = Theme::load(&settings, &themes, &asset_server);
+    ui_font.load(&settings, &theme, &asset_server);
 
     for deck in 0..deck_data.decks.len() {
         if !enabled_json.check_disabled(&deck) && !enabled_json.check_enabled(&deck) {
@@ -99,16 +202,3 @@ fn setup_game(
     }
     enabled_json.update();
 }
-
-fn get_color(color: &str) -> Result<Color, String> {
-    match color {
-        "RED" => Ok(Color::RED),
-        "ORANGE" => Ok(Color::ORANGE),
-        "YELLOW" => Ok(Color::YELLOW),
-        "GREEN" => Ok(Color::GREEN),
-        "BLUE" => Ok(Color::BLUE),
-        "PURPLE" => Ok(Color::PURPLE),
-        "PINK" => Ok(Color::PINK),
-        _ => Err(format!("welp, bad color: {}", color)),
-    }
-}
@@ -0,0 +1,171 @@
+use std::{
+    fs,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    export::RunSummary,
+    handle_json::{CurrentRunJson, DeckDataWrapper},
+    http::{read_request, respond, respond_json},
+    settings::Settings,
+    states_and_ui::GameState,
+    timer::RunClock,
+};
+
+pub struct OverlayServerPlugin;
+
+impl Plugin for OverlayServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(OverlayServer::default())
+            // settings.ini is read in setup_game, so wait until after it
+            .add_startup_system_to_stage(StartupStage::PostStartup, start_overlay_server)
+            .add_system(publish_run_state);
+    }
+}
+
+const OVERLAY_PAGE: &str = "assets/overlay/index.html";
+const CLOCK_INTERVAL: f64 = 0.5; // the run clock isnt part of the run, so while playing resend it this often
+
+// stored in the [Overlay] section of settings.ini
+#[derive(Debug, Clone)]
+pub struct OverlaySettings {
+    pub enabled: bool,
+    pub address: String,
+    pub port: u16,
+}
+
+impl OverlaySettings {
+    pub fn load(settings: &Settings) -> Self {
+        OverlaySettings {
+            enabled: matches!(
+                settings.settings.getbool("Overlay", "enabled"),
+                Ok(Some(true))
+            ),
+            address: settings
+                .settings
+                .get("Overlay", "address")
+                .unwrap_or_else(|| "127.0.0.1".to_owned()),
+            port: match settings.settings.getuint("Overlay", "port") {
+                Ok(Some(port)) if port <= u16::MAX as u64 => port as u16,
+                _ => 7878,
+            },
+        }
+    }
+}
+
+// what /state serves
+#[derive(Serialize)]
+struct OverlayState<'a> {
+    playing: bool, // false in the menus, the run is the last one that was played
+    run: &'a RunSummary,
+}
+
+// the newest state for the server thread to hand out
+#[derive(Default)]
+pub struct OverlayServer {
+    running: bool,
+    state: Arc<Mutex<String>>,
+}
+
+fn start_overlay_server(settings: Res<Settings>, mut server: ResMut<OverlayServer>) {
+    let overlay = OverlaySettings::load(&settings);
+    if !overlay.enabled {
+        return;
+    }
+
+    let listener = match TcpListener::bind((overlay.address.as_str(), overlay.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!(
+                "Could not start the overlay server on {}:{}: {}",
+                overlay.address, overlay.port, e
+            );
+            return;
+        }
+    };
+    println!(
+        "Overlay running at http://{}:{}/",
+        overlay.address, overlay.port
+    );
+
+    server.running = true;
+    let state = server.state.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = state.clone();
+            thread::spawn(move || handle_connection(stream, &state));
+        }
+    });
+}
+
+fn handle_connection(mut stream: TcpStream, state: &Mutex<String>) {
+    let request = match read_request(&stream) {
+        Ok(request) => request,
+        Err(_) => return,
+    };
+
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") | ("GET", "/overlay") => match fs::read(OVERLAY_PAGE) {
+            Ok(page) => respond(&mut stream, "200 OK", "text/html; charset=utf-8", &page),
+            Err(_) => respond(
+                &mut stream,
+                "404 Not Found",
+                "text/plain",
+                b"no overlay page",
+            ),
+        },
+        ("GET", "/state") => {
+            let json = state.lock().unwrap().clone();
+            respond_json(&mut stream, "200 OK", &json)
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"not found"),
+    };
+    if let Err(e) = result {
+        println!("Overlay connection failed: {}", e);
+    }
+}
+
+fn publish_run_state(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    server: Res<OverlayServer>,
+    current_run_json: Res<CurrentRunJson>,
+    clock: Res<RunClock>,
+    deck_data: Res<DeckDataWrapper>,
+    mut last_publish: Local<Option<f64>>,
+    mut last_playing: Local<bool>,
+) {
+    if !server.running {
+        return;
+    }
+
+    let now = time.seconds_since_startup();
+    let playing = *state.current() == GameState::InGame;
+    let changed = current_run_json.is_changed() || playing != *last_playing;
+    let publish = match *last_publish {
+        Some(last) => changed || (playing && now - last >= CLOCK_INTERVAL),
+        None => true,
+    };
+    if !publish {
+        return;
+    }
+    *last_publish = Some(now);
+    *last_playing = playing;
+
+    let mut summary = RunSummary::new(&current_run_json, &deck_data);
+    if playing {
+        summary = summary.with_clock(&clock, &current_run_json);
+    }
+    match serde_json::to_string(&OverlayState {
+        playing,
+        run: &summary,
+    }) {
+        Ok(json) => *server.state.lock().unwrap() = json,
+        Err(e) => println!("Could not write the overlay state: {}", e),
+    }
+}
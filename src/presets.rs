@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+
+use crate::{
+    button_input::{spawn_button, spawn_text_input, TextInput},
+    handle_json::{DeckDataWrapper, DeckPreset, EnabledJson, PresetsJson},
+    localization::Locale,
+    states_and_ui::{DialogData, GameState, MenuData, MenuItems},
+    theme::Theme,
+};
+
+pub struct PresetPlugin;
+
+impl Plugin for PresetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::DeckSelection).with_system(setup_presets),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::DeckSelection)
+                .with_system(handle_preset_buttons)
+                .with_system(update_preset_labels),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresetItems {
+    Switch, // go to the next preset
+    Create, // save the enabled decks as a preset
+    Export,
+}
+
+#[derive(Component)]
+pub struct PresetName {
+    // the text input used for naming new presets
+}
+
+fn setup_presets(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    mut menu_data: ResMut<MenuData>,
+    mut presets: ResMut<PresetsJson>,
+) {
+    // pick up any presets that were added since the last time
+    presets.load();
+
+    let size = Vec2::new(250.0, 80.0);
+
+    menu_data.button_entity.push(spawn_button(
+        &mut commands,
+        &theme,
+        "",
+        30.0,
+        10.0,
+        410.0,
+        size,
+        MenuItems::Preset(PresetItems::Switch),
+    ));
+
+    let name = spawn_text_input(
+        &mut commands,
+        &theme,
+        &locale.get("preset.placeholder"),
+        30.0,
+        10.0,
+        320.0,
+        size,
+    );
+    commands.entity(name).insert(PresetName {});
+    menu_data.button_entity.push(name);
+
+    let buttons = [
+        (PresetItems::Create, "preset.save", 230.0),
+        (PresetItems::Export, "preset.export", 140.0),
+    ];
+    for (item, key, y) in buttons {
+        menu_data.button_entity.push(spawn_button(
+            &mut commands,
+            &theme,
+            &locale.get(key),
+            30.0,
+            10.0,
+            y,
+            size,
+            MenuItems::Preset(item),
+        ));
+    }
+}
+
+fn handle_preset_buttons(
+    mut presets: ResMut<PresetsJson>,
+    mut enabled_json: ResMut<EnabledJson>,
+    deck_data: Res<DeckDataWrapper>,
+    dialog_data: Res<DialogData>,
+    locale: Res<Locale>,
+    mut name_query: Query<&mut TextInput, With<PresetName>>,
+    query: Query<(&Interaction, &MenuItems), (With<Button>, Changed<Interaction>)>,
+) {
+    if dialog_data.is_open() {
+        return;
+    }
+
+    for (interaction, menu_item) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match menu_item {
+            MenuItems::Preset(PresetItems::Switch) => {
+                if presets.presets.is_empty() {
+                    continue;
+                }
+
+                // go to the preset after the current one, or the first one if the decks were changed
+                let next = match presets.active(&enabled_json, &deck_data) {
+                    Some(i) => (i + 1) % presets.presets.len(),
+                    None => 0,
+                };
+                // like any other change this stays unsaved until Save, so Back still asks about it
+                presets.presets[next].apply(&mut enabled_json, &deck_data);
+            }
+            MenuItems::Preset(PresetItems::Create) => {
+                let mut name = String::new();
+                for mut input in name_query.iter_mut() {
+                    name = input.value.trim().to_owned();
+                    input.value.clear();
+                }
+                if name.is_empty() {
+                    name = locale.format("preset.default_name", &[&(presets.presets.len() + 1)]);
+                }
+
+                let preset = DeckPreset::new(&name, &enabled_json, &deck_data);
+                match presets.save(preset) {
+                    Ok(path) => println!("Saved preset to {}", path.display()),
+                    Err(err) => println!("Could not save preset: {}", err),
+                }
+            }
+            MenuItems::Preset(PresetItems::Export) => {
+                match presets.active(&enabled_json, &deck_data) {
+                    Some(i) => match presets.presets[i].export() {
+                        Ok(path) => println!("Exported preset to {}", path.display()),
+                        Err(err) => println!("Could not export preset: {}", err),
+                    },
+                    None => println!("The enabled decks are not a preset, save them as one first"),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_preset_labels(
+    presets: Res<PresetsJson>,
+    enabled_json: Res<EnabledJson>,
+    deck_data: Res<DeckDataWrapper>,
+    locale: Res<Locale>,
+    query: Query<(&MenuItems, &Children)>,
+    added_query: Query<(), Added<MenuItems>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !presets.is_changed() && !enabled_json.is_changed() && added_query.is_empty() {
+        // finding the active preset goes through every deck, so only do it when something changed
+        return;
+    }
+
+    for (menu_item, children) in query.iter() {
+        let label = match menu_item {
+            MenuItems::Preset(PresetItems::Switch) => {
+                match presets.active(&enabled_json, &deck_data) {
+                    Some(i) => locale.format("preset.active", &[&presets.presets[i].name]),
+                    None if presets.presets.is_empty() => locale.get("preset.none"),
+                    None => locale.get("preset.custom"),
+                }
+            }
+            _ => continue,
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::Distribution};
+
+use crate::{
+    button_input::{spawn_button, spawn_text},
+    constants::GameGlobals,
+    deck_filter::DeckFilter,
+    handle_json::{CurrentRunJson, DeckDataWrapper, EnabledJson, RunCommand, RunHistoryJson},
+    localization::Locale,
+    settings::Settings,
+    states_and_ui::{DeckNumber, DialogData, GameState, MenuData, MenuItems},
+    stats::deck_stats,
+    theme::Theme,
+};
+
+pub struct RandomizePlugin;
+
+impl Plugin for RandomizePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DeckLocks {
+            decks: HashSet::new(),
+        })
+        .insert_resource(Weighting::Even)
+        .add_system_set(SystemSet::on_enter(GameState::PreGame).with_system(setup_randomize))
+        .add_system_set(
+            SystemSet::on_update(GameState::PreGame)
+                .with_system(handle_randomize_buttons)
+                .with_system(handle_lock_and_reroll)
+                .with_system(update_randomize_labels),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RandomizeItems {
+    Weighting,
+    DecksPerGame,
+}
+
+// decks that survive a randomize, only used before the run starts
+pub struct DeckLocks {
+    pub decks: HashSet<usize>,
+}
+
+// how likely every deck is to get picked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weighting {
+    Even,
+    Tag,   // decks matching the tag filter are more likely
+    Plays, // decks that have been played less are more likely
+}
+
+impl Weighting {
+    fn next(self) -> Self {
+        match self {
+            Weighting::Even => Weighting::Tag,
+            Weighting::Tag => Weighting::Plays,
+            Weighting::Plays => Weighting::Even,
+        }
+    }
+
+    // the lang file key for the button
+    fn key(self) -> &'static str {
+        match self {
+            Weighting::Even => "randomize.weight_even",
+            Weighting::Tag => "randomize.weight_tag",
+            Weighting::Plays => "randomize.weight_plays",
+        }
+    }
+}
+
+// the enabled decks that could be drawn, with how likely they are to get drawn
+fn deck_pool(
+    weighting: Weighting,
+    filter: &DeckFilter,
+    deck_data: &DeckDataWrapper,
+    enabled_json: &EnabledJson,
+    run_history: &RunHistoryJson,
+) -> Vec<(usize, f64)> {
+    let stats = deck_stats(run_history);
+
+    enabled_json
+        .enabled
+        .iter()
+        .filter_map(|deck| {
+            let data = &deck_data.decks[*deck];
+
+            let weight = match weighting {
+                // the tag filter only limits the pool when it isnt used for weighting
+                Weighting::Even if !filter.matches_tag(data) => return None,
+                Weighting::Plays if !filter.matches_tag(data) => return None,
+                Weighting::Even => 1.0,
+                Weighting::Tag if filter.tag.is_some() && filter.matches_tag(data) => 4.0,
+                Weighting::Tag => 1.0,
+                Weighting::Plays => {
+                    let plays = stats.get(deck).map(|stats| stats.plays).unwrap_or(0);
+                    1.0 / (1.0 + plays as f64)
+                }
+            };
+            Some((*deck, weight))
+        })
+        .collect()
+}
+
+// draw one deck from the pool, ignoring decks that are already picked
+fn draw_deck(pool: &[(usize, f64)], picked: &[usize]) -> Option<usize> {
+    let pool: Vec<&(usize, f64)> = pool
+        .iter()
+        .filter(|(deck, _)| !picked.contains(deck))
+        .collect();
+
+    let weights = WeightedIndex::new(pool.iter().map(|(_, weight)| *weight)).ok()?;
+    Some(pool[weights.sample(&mut rand::thread_rng())].0)
+}
+
+fn setup_randomize(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    mut menu_data: ResMut<MenuData>,
+    mut locks: ResMut<DeckLocks>,
+) {
+    locks.decks.clear();
+
+    let size = Vec2::new(250.0, 80.0);
+
+    let buttons = [
+        (
+            MenuItems::Randomize,
+            locale.get("randomize.randomize"),
+            600.0,
+        ),
+        (
+            MenuItems::Randomizer(RandomizeItems::Weighting),
+            String::new(),
+            500.0,
+        ),
+        (
+            MenuItems::Randomizer(RandomizeItems::DecksPerGame),
+            String::new(),
+            400.0,
+        ),
+    ];
+    for (item, label, y) in buttons {
+        menu_data.button_entity.push(spawn_button(
+            &mut commands,
+            &theme,
+            &label,
+            30.0,
+            10.0,
+            y,
+            size,
+            item,
+        ));
+    }
+
+    menu_data.button_entity.push(spawn_text(
+        &mut commands,
+        &locale.get("randomize.hint"),
+        &theme,
+        24.0,
+        10.0,
+        720.0,
+    ));
+}
+
+fn handle_randomize_buttons(
+    mut weighting: ResMut<Weighting>,
+    mut globals: ResMut<GameGlobals>,
+    mut settings: ResMut<Settings>,
+    mut current_run_json: ResMut<CurrentRunJson>,
+    locks: Res<DeckLocks>,
+    filter: Res<DeckFilter>,
+    deck_data: Res<DeckDataWrapper>,
+    enabled_json: Res<EnabledJson>,
+    run_history: Res<RunHistoryJson>,
+    dialog_data: Res<DialogData>,
+    query: Query<(&Interaction, &MenuItems), (With<Button>, Changed<Interaction>)>,
+) {
+    if dialog_data.is_open() {
+        return;
+    }
+
+    for (interaction, menu_item) in query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match menu_item {
+            // this replaces the Auto-pick button from deck categories: with even weighting
+            // only decks matching the tag filter are drawn, which is what auto-pick did
+            MenuItems::Randomize => {
+                // drop every deck that isnt locked, every step is undoable
+                for deck in current_run_json.decks.clone().iter().rev() {
+                    if !locks.decks.contains(deck) {
+                        let command = current_run_json.toggle_deck_command(*deck);
+                        current_run_json.apply(command);
+                    }
+                }
+
+                let pool = deck_pool(*weighting, &filter, &deck_data, &enabled_json, &run_history);
+                while current_run_json.decks.len() < globals.decks_per_game {
+                    let deck = match draw_deck(&pool, &current_run_json.decks) {
+                        Some(deck) => deck,
+                        None => break, // ran out of decks
+                    };
+                    let command = current_run_json.toggle_deck_command(deck);
+                    current_run_json.apply(command);
+                }
+            }
+            MenuItems::Randomizer(RandomizeItems::Weighting) => *weighting = weighting.next(),
+            MenuItems::Randomizer(RandomizeItems::DecksPerGame) => {
+                // count up to the number of enabled decks, then wrap back around
+                globals.decks_per_game += 1;
+                if globals.decks_per_game > enabled_json.enabled.len().max(1) {
+                    globals.decks_per_game = 1;
+                }
+
+                settings.settings.set(
+                    "Game",
+                    "decks_per_game",
+                    Some(globals.decks_per_game.to_string()),
+                );
+                settings.update();
+            }
+            _ => {}
+        }
+    }
+}
+
+// right click a picked deck to swap it for another one, middle click to lock it
+fn handle_lock_and_reroll(
+    mouse: Res<Input<MouseButton>>,
+    weighting: Res<Weighting>,
+    mut locks: ResMut<DeckLocks>,
+    mut current_run_json: ResMut<CurrentRunJson>,
+    filter: Res<DeckFilter>,
+    deck_data: Res<DeckDataWrapper>,
+    enabled_json: Res<EnabledJson>,
+    run_history: Res<RunHistoryJson>,
+    dialog_data: Res<DialogData>,
+    query: Query<(&Interaction, &DeckNumber), With<Button>>,
+) {
+    let right = mouse.just_pressed(MouseButton::Right);
+    let middle = mouse.just_pressed(MouseButton::Middle);
+
+    if dialog_data.is_open() || (!right && !middle) {
+        return;
+    }
+
+    let deck = match query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Hovered)
+    {
+        Some((_, deck_num)) => deck_num.num,
+        None => return,
+    };
+
+    let index = match current_run_json.decks.iter().position(|x| *x == deck) {
+        Some(index) => index,
+        None => return, // only picked decks can be locked or rerolled
+    };
+
+    if middle {
+        if !locks.decks.remove(&deck) {
+            locks.decks.insert(deck);
+        }
+        return;
+    }
+
+    if locks.decks.contains(&deck) {
+        return;
+    }
+
+    let pool = deck_pool(*weighting, &filter, &deck_data, &enabled_json, &run_history);
+    if let Some(new_deck) = draw_deck(&pool, &current_run_json.decks) {
+        // swap it in the same spot so the order stays the same
+        let command = current_run_json.toggle_deck_command(deck);
+        current_run_json.apply(command);
+        current_run_json.apply(RunCommand::EnableDeck {
+            deck: new_deck,
+            index,
+            completed: false,
+            drawn: None,
+        });
+    }
+}
+
+fn update_randomize_labels(
+    weighting: Res<Weighting>,
+    globals: Res<GameGlobals>,
+    locale: Res<Locale>,
+    query: Query<(&MenuItems, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (menu_item, children) in query.iter() {
+        let label = match menu_item {
+            MenuItems::Randomizer(RandomizeItems::Weighting) => locale.get(weighting.key()),
+            MenuItems::Randomizer(RandomizeItems::DecksPerGame) => {
+                locale.format("randomize.decks", &[&globals.decks_per_game])
+            }
+            _ => continue,
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use std::{
+    fs,
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::{
+    export::RunSummary,
+    handle_json::{CurrentRunJson, DeckDataWrapper},
+    http::{read_request, respond, respond_json, Request},
+    run_actions::{RunAction, RunActionRejected},
+    settings::Settings,
+    states_and_ui::GameState,
+    timer::RunClock,
+};
+
+pub struct RemotePlugin;
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RemoteControl {
+            receiver: None,
+            waiting: Vec::new(),
+        })
+        // settings.ini is read in setup_game, so wait until after it
+        .add_startup_system_to_stage(StartupStage::PostStartup, start_remote_server)
+        .add_system(receive_remote_actions.before("run_actions"))
+        .add_system(answer_remote_requests.after("run_actions"));
+    }
+}
+
+const REMOTE_PAGE: &str = "assets/remote/index.html";
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_CONNECTIONS: usize = 8; // each one gets a thread, anything past this is turned away
+
+// stored in the [Remote] section of settings.ini
+#[derive(Debug, Clone)]
+pub struct RemoteSettings {
+    pub enabled: bool,
+    pub address: String,
+    pub port: u16,
+    pub token: String, // has to be sent with every api request
+}
+
+impl RemoteSettings {
+    pub fn load(settings: &Settings) -> Self {
+        RemoteSettings {
+            enabled: matches!(
+                settings.settings.getbool("Remote", "enabled"),
+                Ok(Some(true))
+            ),
+            address: settings
+                .settings
+                .get("Remote", "address")
+                .unwrap_or_else(|| "0.0.0.0".to_owned()),
+            port: match settings.settings.getuint("Remote", "port") {
+                Ok(Some(port)) if port <= u16::MAX as u64 => port as u16,
+                _ => 7880,
+            },
+            token: settings.settings.get("Remote", "token").unwrap_or_default(),
+        }
+    }
+}
+
+// the run as json, or why the action couldnt be done
+type Answer = Result<String, String>;
+
+// an api call for the game to answer, action is None when only the state was asked for
+struct RemoteRequest {
+    action: Option<RunAction>,
+    answer: Sender<Answer>,
+}
+
+pub struct RemoteControl {
+    receiver: Option<Mutex<Receiver<RemoteRequest>>>,
+    waiting: Vec<(Option<RunAction>, Sender<Answer>)>, // answered once the actions have been applied
+}
+
+fn start_remote_server(mut settings: ResMut<Settings>, mut remote: ResMut<RemoteControl>) {
+    let mut remote_settings = RemoteSettings::load(&settings);
+    if !remote_settings.enabled {
+        return;
+    }
+
+    if remote_settings.token.is_empty() {
+        // never leave the api open to the whole network without a token
+        remote_settings.token = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(24)
+            .map(char::from)
+            .collect();
+        settings
+            .settings
+            .set("Remote", "token", Some(remote_settings.token.clone()));
+        settings.update();
+    }
+
+    let listener = match TcpListener::bind((remote_settings.address.as_str(), remote_settings.port))
+    {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!(
+                "Could not start the remote control on {}:{}: {}",
+                remote_settings.address, remote_settings.port, e
+            );
+            return;
+        }
+    };
+    println!(
+        "Remote control running on port {}, the token is in config/settings.ini",
+        remote_settings.port
+    );
+
+    let (sender, receiver) = mpsc::channel();
+    remote.receiver = Some(Mutex::new(receiver));
+
+    let token = remote_settings.token;
+    let open = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                open.fetch_sub(1, Ordering::SeqCst);
+                let _ = respond_json(
+                    &mut stream,
+                    "503 Service Unavailable",
+                    &error_json("too many connections"),
+                );
+                continue;
+            }
+
+            let slot = ConnectionSlot(open.clone());
+            let sender = sender.clone();
+            let token = token.clone();
+            thread::spawn(move || {
+                handle_connection(stream, &token, &sender);
+                drop(slot);
+            });
+        }
+    });
+}
+
+// counts towards MAX_CONNECTIONS until it is dropped, even if the connection's thread panics
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// the token can be in an "Authorization: Bearer <token>" header, or ?token= for links on a phone
+fn authorized(request: &Request, token: &str) -> bool {
+    let given = request
+        .header("authorization")
+        .and_then(|x| x.strip_prefix("Bearer "))
+        .or_else(|| request.query.get("token").map(|x| x.as_str()));
+
+    match given {
+        // compare every byte so the time taken doesnt give away how much was right
+        Some(given) => {
+            given.len() == token.len()
+                && given
+                    .bytes()
+                    .zip(token.bytes())
+                    .fold(0, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        }
+        None => false,
+    }
+}
+
+// which deck in the run, counting from 0 like the run events do
+fn deck_index(request: &Request) -> Option<usize> {
+    request.query.get("index")?.parse().ok()
+}
+
+fn parse_action(request: &Request) -> Result<RunAction, &'static str> {
+    let index = || deck_index(request).ok_or("index is missing or not a number");
+
+    match request.path.trim_start_matches("/api/") {
+        "complete" => Ok(RunAction::Complete(index()?)),
+        "uncomplete" => Ok(RunAction::Uncomplete(index()?)),
+        "toggle" => Ok(RunAction::ToggleInProgress(index()?)),
+        "reroll" => Ok(RunAction::Reroll {
+            deck: index()?,
+            primary: match request.query.get("card").map(|x| x.as_str()) {
+                None | Some("primary") => true,
+                Some("secondary") => false,
+                Some(_) => return Err("card has to be primary or secondary"),
+            },
+        }),
+        "undo" => Ok(RunAction::Undo),
+        "redo" => Ok(RunAction::Redo),
+        "advance" => Ok(RunAction::Advance),
+        _ => Err("unknown action"),
+    }
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn handle_connection(mut stream: TcpStream, token: &str, sender: &Sender<RemoteRequest>) {
+    let request = match read_request(&stream) {
+        Ok(request) => request,
+        Err(_) => return,
+    };
+
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        // the page itself has no secrets, it asks for the token
+        ("GET", "/") => match fs::read(REMOTE_PAGE) {
+            Ok(page) => respond(&mut stream, "200 OK", "text/html; charset=utf-8", &page),
+            Err(_) => respond(
+                &mut stream,
+                "404 Not Found",
+                "text/plain",
+                b"no remote page",
+            ),
+        },
+        // browsers check before sending the Authorization header
+        ("OPTIONS", _) => respond(&mut stream, "204 No Content", "text/plain", b""),
+        (_, path) if path.starts_with("/api/") && !authorized(&request, token) => respond_json(
+            &mut stream,
+            "401 Unauthorized",
+            &error_json("missing or wrong token"),
+        ),
+        ("GET", "/api/state") => ask_game(&mut stream, sender, None),
+        ("POST", path) if path.starts_with("/api/") => match parse_action(&request) {
+            Ok(action) => ask_game(&mut stream, sender, Some(action)),
+            Err(message) => respond_json(&mut stream, "400 Bad Request", &error_json(message)),
+        },
+        _ => respond_json(&mut stream, "404 Not Found", &error_json("not found")),
+    };
+    if let Err(e) = result {
+        println!("Remote control connection failed: {}", e);
+    }
+}
+
+// hand the request to the game and answer with the run once it has been applied
+fn ask_game(
+    stream: &mut TcpStream,
+    sender: &Sender<RemoteRequest>,
+    action: Option<RunAction>,
+) -> std::io::Result<()> {
+    let (answer, answer_receiver) = mpsc::channel();
+    if sender.send(RemoteRequest { action, answer }).is_err() {
+        return respond_json(
+            stream,
+            "503 Service Unavailable",
+            &error_json("game closed"),
+        );
+    }
+
+    match answer_receiver.recv_timeout(ANSWER_TIMEOUT) {
+        Ok(Ok(json)) => respond_json(stream, "200 OK", &json),
+        Ok(Err(message)) => respond_json(stream, "409 Conflict", &error_json(&message)),
+        Err(_) => respond_json(
+            stream,
+            "503 Service Unavailable",
+            &error_json("the game did not answer"),
+        ),
+    }
+}
+
+// api actions go through the same RunAction path as clicking the board
+fn receive_remote_actions(
+    state: Res<State<GameState>>,
+    mut remote: ResMut<RemoteControl>,
+    mut run_actions: EventWriter<RunAction>,
+) {
+    let requests: Vec<RemoteRequest> = match &remote.receiver {
+        Some(receiver) => receiver.lock().unwrap().try_iter().collect(),
+        None => return,
+    };
+
+    for request in requests {
+        if let Some(action) = request.action {
+            // undo and redo also work before the game starts, but not from the api
+            if *state.current() != GameState::InGame {
+                let _ = request
+                    .answer
+                    .send(Err("no run is being played".to_owned()));
+                continue;
+            }
+            run_actions.send(action);
+        }
+        remote.waiting.push((request.action, request.answer));
+    }
+}
+
+fn answer_remote_requests(
+    state: Res<State<GameState>>,
+    mut remote: ResMut<RemoteControl>,
+    current_run_json: Res<CurrentRunJson>,
+    clock: Res<RunClock>,
+    deck_data: Res<DeckDataWrapper>,
+    mut rejected_events: EventReader<RunActionRejected>,
+) {
+    // read them every frame, so old ones dont get matched to a later request
+    let mut rejected: Vec<RunActionRejected> = rejected_events.iter().copied().collect();
+    if remote.waiting.is_empty() {
+        return;
+    }
+
+    let mut summary = RunSummary::new(&current_run_json, &deck_data);
+    if *state.current() == GameState::InGame {
+        summary = summary.with_clock(&clock, &current_run_json);
+    }
+    let json = serde_json::to_string(&summary).map_err(|e| e.to_string());
+    for (action, answer) in remote.waiting.drain(..) {
+        // a board click can send the same action in the same frame, then either one can get the rejection
+        let rejection = rejected
+            .iter()
+            .position(|x| Some(x.action) == action)
+            .map(|i| rejected.remove(i));
+        let _ = match rejection {
+            Some(rejection) => answer.send(Err(rejection.reason.to_owned())),
+            None => answer.send(json.clone()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::http::parse_query;
+
+    fn request(method: &str, target: &str, headers: &[(&str, &str)]) -> Request {
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, parse_query(query)),
+            None => (target, HashMap::new()),
+        };
+        Request {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            query,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn authorized_takes_the_header_or_the_query() {
+        let header = request("GET", "/api/state", &[("authorization", "Bearer secret")]);
+        assert!(authorized(&header, "secret"));
+
+        let query = request("GET", "/api/state?token=secret", &[]);
+        assert!(authorized(&query, "secret"));
+    }
+
+    #[test]
+    fn authorized_rejects_wrong_or_missing_tokens() {
+        assert!(!authorized(&request("GET", "/api/state", &[]), "secret"));
+        assert!(!authorized(
+            &request("GET", "/api/state?token=secreT", &[]),
+            "secret"
+        ));
+        // a prefix of the token isnt the token
+        assert!(!authorized(
+            &request("GET", "/api/state?token=secre", &[]),
+            "secret"
+        ));
+        assert!(!authorized(
+            &request("GET", "/api/state", &[("authorization", "secret")]),
+            "secret"
+        ));
+    }
+
+    #[test]
+    fn parse_action_reads_the_deck_actions() {
+        let parse = |target| parse_action(&request("POST", target, &[]));
+
+        assert_eq!(parse("/api/complete?index=2"), Ok(RunAction::Complete(2)));
+        assert_eq!(
+            parse("/api/uncomplete?index=0"),
+            Ok(RunAction::Uncomplete(0))
+        );
+        assert_eq!(
+            parse("/api/toggle?index=4"),
+            Ok(RunAction::ToggleInProgress(4))
+        );
+        assert_eq!(
+            parse("/api/reroll?index=1"),
+            Ok(RunAction::Reroll {
+                deck: 1,
+                primary: true
+            })
+        );
+        assert_eq!(
+            parse("/api/reroll?index=1&card=secondary"),
+            Ok(RunAction::Reroll {
+                deck: 1,
+                primary: false
+            })
+        );
+        assert_eq!(parse("/api/undo"), Ok(RunAction::Undo));
+        assert_eq!(parse("/api/redo"), Ok(RunAction::Redo));
+        assert_eq!(parse("/api/advance"), Ok(RunAction::Advance));
+    }
+
+    #[test]
+    fn parse_action_rejects_bad_requests() {
+        let parse = |target| parse_action(&request("POST", target, &[]));
+
+        assert!(parse("/api/complete").is_err());
+        assert!(parse("/api/complete?index=two").is_err());
+        assert!(parse("/api/complete?index=-1").is_err());
+        assert!(parse("/api/reroll?index=1&card=both").is_err());
+        assert!(parse("/api/explode?index=1").is_err());
+    }
+}
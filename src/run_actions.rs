@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    deck::Decks,
+    handle_json::{CurrentRunJson, RunCommand},
+    states_and_ui::GameState,
+    timer::RunClock,
+};
+
+pub struct RunActionPlugin;
+
+impl Plugin for RunActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RunAction>()
+            .add_event::<RunActionRejected>()
+            .add_system(apply_run_actions.label("run_actions"));
+    }
+}
+
+// everything a player can do to a run, from clicking the board, the buttons, the keyboard or the remote control api.
+// decks are referred to by their position in the run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunAction {
+    ToggleInProgress(usize),
+    Complete(usize),
+    Uncomplete(usize),
+    Reroll { deck: usize, primary: bool },
+    Undo,
+    Redo,
+    Advance, // finish the deck being played and start the next one that isnt completed
+}
+
+impl RunAction {
+    // the deck it changes, if it is about a single deck
+    pub fn deck(self) -> Option<usize> {
+        match self {
+            RunAction::ToggleInProgress(deck)
+            | RunAction::Complete(deck)
+            | RunAction::Uncomplete(deck)
+            | RunAction::Reroll { deck, .. } => Some(deck),
+            RunAction::Undo | RunAction::Redo | RunAction::Advance => None,
+        }
+    }
+}
+
+// picks from every card except the current one, None if there is nothing else to draw
+fn reroll_command(
+    current_run_json: &CurrentRunJson,
+    decks: &Decks,
+    deck: usize,
+    primary: bool,
+) -> Option<RunCommand> {
+    let together = decks.0.get(deck)?;
+    let drawn = current_run_json.drawn.get(deck)?;
+    let (cards, from) = match primary {
+        true => (together.primary.cards, drawn.primary),
+        false => (together.secondary.cards, drawn.secondary),
+    };
+
+    if cards < 2 {
+        return None;
+    }
+
+    let mut to = rand::thread_rng().gen_range(0..cards - 1);
+    if to >= from {
+        to += 1;
+    }
+
+    Some(RunCommand::Reroll {
+        deck,
+        primary,
+        from,
+        to,
+    })
+}
+
+// an action that couldnt be done, e.g. completing a deck that is already completed
+#[derive(Clone, Copy, Debug)]
+pub struct RunActionRejected {
+    pub action: RunAction,
+    pub reason: &'static str,
+}
+
+fn apply_run_actions(
+    state: Res<State<GameState>>,
+    decks: Option<Res<Decks>>,
+    mut current_run_json: ResMut<CurrentRunJson>,
+    mut clock: ResMut<RunClock>,
+    mut actions: EventReader<RunAction>,
+    mut rejected: EventWriter<RunActionRejected>,
+) {
+    let actions: Vec<RunAction> = actions.iter().copied().collect();
+    if actions.is_empty() {
+        return;
+    }
+
+    let in_game = *state.current() == GameState::InGame;
+
+    if in_game {
+        // splits and completion times are taken from the run, so bring it up to date first
+        clock.write(&mut current_run_json);
+    }
+
+    for action in actions {
+        let result = apply_run_action(
+            action,
+            &mut current_run_json,
+            decks.as_deref(),
+            *state.current(),
+        );
+        if let Err(reason) = result {
+            rejected.send(RunActionRejected { action, reason });
+        }
+    }
+}
+
+fn apply_run_action(
+    action: RunAction,
+    current_run_json: &mut CurrentRunJson,
+    decks: Option<&Decks>,
+    state: GameState,
+) -> Result<(), &'static str> {
+    let in_game = state == GameState::InGame;
+    // deck selection can only be undone before the game starts
+    let allow_selection = state == GameState::PreGame;
+
+    // api requests can name any deck, so check it is actually in the run
+    let count = current_run_json.completed_decks.len();
+    if let Some(deck) = action.deck() {
+        if !in_game {
+            return Err("no run is being played");
+        }
+        if deck >= count {
+            return Err("there is no deck at that index");
+        }
+    }
+
+    match action {
+        RunAction::Undo => match current_run_json.undo(allow_selection) {
+            true => Ok(()),
+            false => Err("there is nothing to undo"),
+        },
+        RunAction::Redo => match current_run_json.redo(allow_selection) {
+            true => Ok(()),
+            false => Err("there is nothing to redo"),
+        },
+        RunAction::ToggleInProgress(deck)
+            if current_run_json.completed_decks[deck]
+                && current_run_json.timers.current != Some(deck) =>
+        {
+            Err("the deck is already completed")
+        }
+        RunAction::ToggleInProgress(deck) => {
+            current_run_json.toggle_in_progress(deck);
+            Ok(())
+        }
+        RunAction::Complete(deck) if current_run_json.completed_decks[deck] => {
+            Err("the deck is already completed")
+        }
+        RunAction::Complete(deck) => {
+            current_run_json.apply(RunCommand::CompleteDeck(deck));
+            Ok(())
+        }
+        RunAction::Uncomplete(deck) if !current_run_json.completed_decks[deck] => {
+            Err("the deck isnt completed")
+        }
+        RunAction::Uncomplete(deck) => {
+            current_run_json.apply(RunCommand::UncompleteDeck(deck));
+            Ok(())
+        }
+        RunAction::Reroll { deck, primary } => {
+            let command = decks
+                .and_then(|decks| reroll_command(current_run_json, decks, deck, primary))
+                .ok_or("there is no other card to draw")?;
+            current_run_json.apply(command);
+            Ok(())
+        }
+        RunAction::Advance if !in_game => Err("no run is being played"),
+        RunAction::Advance => {
+            let start = match current_run_json.timers.current {
+                Some(deck) => {
+                    current_run_json.apply(RunCommand::CompleteDeck(deck));
+                    deck + 1
+                }
+                None => 0,
+            };
+
+            // the next deck after the one that was just finished, wrapping around to any that got skipped
+            let next = (0..count)
+                .map(|i| (start + i) % count)
+                .find(|deck| !current_run_json.completed_decks[*deck]);
+            match next {
+                Some(next) => current_run_json.toggle_in_progress(next),
+                // finishing the last deck still counts, there just isnt a next one
+                None if start > 0 => {}
+                None => return Err("every deck is already completed"),
+            }
+            Ok(())
+        }
+    }
+}
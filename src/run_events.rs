@@ -0,0 +1,364 @@
+use std::{
+    io::ErrorKind,
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+use tungstenite::{Message, WebSocket};
+
+use crate::{
+    export::CardSummary,
+    handle_json::{CurrentRunJson, DeckDataWrapper, DrawnCards},
+    settings::Settings,
+    states_and_ui::GameState,
+};
+
+pub struct RunEventPlugin;
+
+impl Plugin for RunEventPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RunEvent>()
+            .insert_resource(EventStream { sender: None })
+            // settings.ini is read in setup_game, so wait until after it
+            .add_startup_system_to_stage(StartupStage::PostStartup, start_event_stream)
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(run_started))
+            .add_system(detect_run_events.label("run_events"))
+            .add_system(send_run_events.after("run_events"));
+    }
+}
+
+// everything that happens to a run, sent to websocket clients as json, see docs/run_events.md
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunEvent {
+    RunStarted {
+        seed: u64,
+        started: u64,
+        decks: Vec<DealtDeck>,
+    },
+    // picking or unpicking a deck in the pre-game
+    DeckSelected {
+        deck: usize,
+        name: String,
+        selected: bool,
+    },
+    // index is the deck's position in the run, deck is its position in decks.json
+    CardDrawn {
+        index: usize,
+        deck: usize,
+        name: String,
+        primary: CardSummary,
+        secondary: CardSummary,
+    },
+    DeckCompleted {
+        index: usize,
+        deck: usize,
+        name: String,
+        completed: bool,
+    },
+    ScoreChanged {
+        score: usize,
+        decks: usize,
+    },
+    RunFinished {
+        score: usize,
+        time: f64,
+    },
+}
+
+// a deck in the run with the cards it was dealt
+#[derive(Serialize, Debug, Clone)]
+pub struct DealtDeck {
+    pub deck: usize,
+    pub name: String,
+    pub primary: CardSummary,
+    pub secondary: CardSummary,
+}
+
+// what gets written to the socket, the event with the time it happened
+#[derive(Serialize)]
+struct EventMessage<'a> {
+    time: u64, // unix time
+    #[serde(flatten)]
+    event: &'a RunEvent,
+}
+
+// stored in the [Events] section of settings.ini
+#[derive(Debug, Clone)]
+pub struct EventStreamSettings {
+    pub enabled: bool,
+    pub address: String,
+    pub port: u16,
+}
+
+impl EventStreamSettings {
+    pub fn load(settings: &Settings) -> Self {
+        EventStreamSettings {
+            enabled: matches!(
+                settings.settings.getbool("Events", "enabled"),
+                Ok(Some(true))
+            ),
+            address: settings
+                .settings
+                .get("Events", "address")
+                .unwrap_or_else(|| "127.0.0.1".to_owned()),
+            port: match settings.settings.getuint("Events", "port") {
+                Ok(Some(port)) if port <= u16::MAX as u64 => port as u16,
+                _ => 7879,
+            },
+        }
+    }
+}
+
+// hands json to the thread that writes to the clients, so a slow client never holds up the game
+pub struct EventStream {
+    sender: Option<Mutex<Sender<String>>>,
+}
+
+type Clients = Arc<Mutex<Vec<WebSocket<TcpStream>>>>;
+
+// how often the clients get checked for pings and closes while nothing is happening in the run
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn start_event_stream(settings: Res<Settings>, mut stream: ResMut<EventStream>) {
+    let events = EventStreamSettings::load(&settings);
+    if !events.enabled {
+        return;
+    }
+
+    let listener = match TcpListener::bind((events.address.as_str(), events.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!(
+                "Could not start the event stream on {}:{}: {}",
+                events.address, events.port, e
+            );
+            return;
+        }
+    };
+    println!("Run events at ws://{}:{}/", events.address, events.port);
+
+    let clients: Clients = Arc::default();
+    let (sender, receiver) = mpsc::channel();
+    stream.sender = Some(Mutex::new(sender));
+
+    let accepted = clients.clone();
+    thread::spawn(move || {
+        for tcp in listener.incoming().flatten() {
+            let accepted = accepted.clone();
+            // the handshake waits on the client, so it gets its own thread
+            thread::spawn(move || {
+                let _ = tcp.set_write_timeout(Some(Duration::from_secs(1)));
+                match tungstenite::accept(tcp) {
+                    Ok(socket) => {
+                        // reads only check for whatever the client sent, they shouldnt wait for more
+                        let _ = socket
+                            .get_ref()
+                            .set_read_timeout(Some(Duration::from_millis(1)));
+                        accepted.lock().unwrap().push(socket);
+                    }
+                    Err(e) => println!("Event stream handshake failed: {}", e),
+                }
+            });
+        }
+    });
+    thread::spawn(move || broadcast(receiver, clients));
+}
+
+// write every message to every client, dropping the ones that closed or went away
+fn broadcast(receiver: Receiver<String>, clients: Clients) {
+    loop {
+        let json = match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(json) => Some(json),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+
+        // take the clients out of the lock, so new ones can be added while these are written to
+        let mut sockets = std::mem::take(&mut *clients.lock().unwrap());
+        sockets.retain_mut(|socket| {
+            read_client(socket)
+                && match &json {
+                    Some(json) => socket.write_message(Message::Text(json.clone())).is_ok(),
+                    None => true,
+                }
+        });
+        clients.lock().unwrap().append(&mut sockets);
+    }
+}
+
+// clients dont send anything but pings and closes, tungstenite queues the answers to those while reading.
+// false once the client has closed the connection
+fn read_client(socket: &mut WebSocket<TcpStream>) -> bool {
+    loop {
+        match socket.read_message() {
+            Ok(Message::Close(_)) => {
+                // send the close back, the connection is done after that
+                let _ = socket.write_pending();
+                return false;
+            }
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                // nothing more to read, send any pongs that were queued
+                return socket.write_pending().is_ok();
+            }
+            Err(_) => return false,
+        }
+    }
+}
+
+// the cards are drawn when the pre-game is left, so the opening deal is only in here and never in card_drawn
+fn run_started(
+    current_run_json: Res<CurrentRunJson>,
+    deck_data: Res<DeckDataWrapper>,
+    mut events: EventWriter<RunEvent>,
+) {
+    let decks = current_run_json
+        .decks
+        .iter()
+        .enumerate()
+        .map(|(index, deck)| {
+            let data = &deck_data.decks[*deck];
+            let drawn = current_run_json
+                .drawn
+                .get(index)
+                .copied()
+                .unwrap_or_default();
+            DealtDeck {
+                deck: *deck,
+                name: data.name.clone(),
+                primary: CardSummary::new(data, true, drawn.primary),
+                secondary: CardSummary::new(data, false, drawn.secondary),
+            }
+        })
+        .collect();
+
+    events.send(RunEvent::RunStarted {
+        seed: current_run_json.seed,
+        started: current_run_json.started,
+        decks,
+    });
+}
+
+// what the run looked like last time it changed, to tell what happened
+#[derive(Default)]
+struct RunSnapshot {
+    decks: Vec<usize>,
+    drawn: Vec<DrawnCards>,
+    completed: Vec<bool>,
+    score: usize,
+    finished: bool,
+}
+
+// picked decks in the pre-game, and drawn cards, completions and the score in game. undo and redo show up here too
+fn detect_run_events(
+    state: Res<State<GameState>>,
+    current_run_json: Res<CurrentRunJson>,
+    deck_data: Res<DeckDataWrapper>,
+    mut last: Local<RunSnapshot>,
+    mut events: EventWriter<RunEvent>,
+) {
+    if !current_run_json.is_changed() {
+        return;
+    }
+    let run = &*current_run_json;
+    let finished = run.is_finished();
+
+    match state.current() {
+        GameState::PreGame => {
+            for deck in run.decks.iter().filter(|x| !last.decks.contains(x)) {
+                events.send(RunEvent::DeckSelected {
+                    deck: *deck,
+                    name: deck_data.decks[*deck].name.clone(),
+                    selected: true,
+                });
+            }
+            for deck in last.decks.iter().filter(|x| !run.decks.contains(x)) {
+                events.send(RunEvent::DeckSelected {
+                    deck: *deck,
+                    name: deck_data.decks[*deck].name.clone(),
+                    selected: false,
+                });
+            }
+        }
+        GameState::InGame if run.decks == last.decks => {
+            for (index, deck) in run.decks.iter().enumerate() {
+                let data = &deck_data.decks[*deck];
+
+                if let (Some(drawn), Some(old)) = (run.drawn.get(index), last.drawn.get(index)) {
+                    if drawn != old {
+                        events.send(RunEvent::CardDrawn {
+                            index,
+                            deck: *deck,
+                            name: data.name.clone(),
+                            primary: CardSummary::new(data, true, drawn.primary),
+                            secondary: CardSummary::new(data, false, drawn.secondary),
+                        });
+                    }
+                }
+                if let (Some(completed), Some(old)) =
+                    (run.completed_decks.get(index), last.completed.get(index))
+                {
+                    if completed != old {
+                        events.send(RunEvent::DeckCompleted {
+                            index,
+                            deck: *deck,
+                            name: data.name.clone(),
+                            completed: *completed,
+                        });
+                    }
+                }
+            }
+            if run.score != last.score {
+                events.send(RunEvent::ScoreChanged {
+                    score: run.score,
+                    decks: run.decks.len(),
+                });
+            }
+            if finished && !last.finished {
+                events.send(RunEvent::RunFinished {
+                    score: run.score,
+                    time: run.timers.run,
+                });
+            }
+        }
+        _ => {}
+    }
+
+    *last = RunSnapshot {
+        decks: run.decks.clone(),
+        drawn: run.drawn.clone(),
+        completed: run.completed_decks.clone(),
+        score: run.score,
+        finished,
+    };
+}
+
+fn send_run_events(stream: Res<EventStream>, mut events: EventReader<RunEvent>) {
+    let sender = match &stream.sender {
+        Some(sender) => sender.lock().unwrap(),
+        None => return,
+    };
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0);
+
+    for event in events.iter() {
+        match serde_json::to_string(&EventMessage { time, event }) {
+            Ok(json) => {
+                let _ = sender.send(json);
+            }
+            Err(e) => println!("Could not write run event: {}", e),
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use crate::{
+    button_input::TextInput,
+    states_and_ui::{grid_position, DialogData, GameState, MenuData, Scrollable},
+    theme::Theme,
+};
+
+pub struct ScrollPlugin;
+
+impl Plugin for ScrollPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BackmapScroll {
+            offset: 0.0,
+            max: 0.0,
+        })
+        .add_system_set(SystemSet::on_enter(GameState::DeckSelection).with_system(setup_scrollbar))
+        .add_system_set(
+            SystemSet::on_update(GameState::DeckSelection)
+                .with_system(scroll_backmap)
+                .with_system(drag_scrollbar)
+                .with_system(update_scrollbar),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::PreGame).with_system(setup_scrollbar))
+        .add_system_set(
+            SystemSet::on_update(GameState::PreGame)
+                .with_system(scroll_backmap)
+                .with_system(drag_scrollbar)
+                .with_system(update_scrollbar),
+        );
+    }
+}
+
+const LINE_HEIGHT: f32 = 70.0; // how far one notch of a mouse wheel scrolls
+const SCROLLBAR_WIDTH: f32 = 20.0;
+const BOTTOM_MARGIN: f32 = 120.0; // keep the last row above the buttons along the bottom
+
+// how far the pre-game and deck selection menus are scrolled
+pub struct BackmapScroll {
+    pub offset: f32, // 0.0 is the top, grows when scrolling down
+    pub max: f32,    // how far down it can go before the last row leaves the screen
+}
+
+impl BackmapScroll {
+    // start back at the top with room for `count` decks in the grid
+    pub fn reset(&mut self, count: usize) {
+        self.offset = 0.0;
+        self.fit_rows(count);
+    }
+
+    // update how far down the grid goes, after decks got hidden or shown
+    pub fn fit_rows(&mut self, count: usize) {
+        let lowest = match count {
+            0 => BOTTOM_MARGIN,
+            _ => grid_position(count - 1).y,
+        };
+        self.max = (BOTTOM_MARGIN - lowest).max(0.0);
+        self.offset = self.offset.clamp(0.0, self.max);
+    }
+}
+
+#[derive(Component)]
+struct Scrollbar {
+    // the track along the right side of the screen
+}
+
+#[derive(Component, Default)]
+struct ScrollThumb {
+    drag: Option<(f32, f32)>, // cursor y and scroll offset when the drag started
+}
+
+fn setup_scrollbar(mut commands: Commands, mut menu_data: ResMut<MenuData>, theme: Res<Theme>) {
+    let track = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(SCROLLBAR_WIDTH), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(0.0),
+                    top: Val::Px(0.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            color: theme.colors.disabled_button.into(),
+            ..Default::default()
+        })
+        .insert(Scrollbar {})
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            top: Val::Px(0.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    color: theme.colors.normal_button.into(),
+                    ..Default::default()
+                })
+                .insert(ScrollThumb::default());
+        })
+        .id();
+    menu_data.button_entity.push(track);
+}
+
+// move everything that scrolls to match the new offset
+fn scroll_to(
+    scroll: &mut BackmapScroll,
+    offset: f32,
+    query: &mut Query<&mut Style, With<Scrollable>>,
+) {
+    let offset = offset.clamp(0.0, scroll.max);
+    let delta = offset - scroll.offset;
+    if delta == 0.0 {
+        return;
+    }
+
+    scroll.offset = offset;
+    for mut style in query.iter_mut() {
+        style.position.bottom += delta;
+    }
+}
+
+// scroll the pre-game and deck selection menus with the mouse wheel or page up/down
+pub fn scroll_backmap(
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut scroll: ResMut<BackmapScroll>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    dialog_data: Res<DialogData>,
+    input_query: Query<&TextInput>,
+    mut query: Query<&mut Style, With<Scrollable>>,
+) {
+    let mut offset = scroll.offset;
+
+    for mouse_wheel_event in mouse_wheel_events.iter() {
+        // still read the events while a dialog is open, so they dont pile up until it closes
+        if dialog_data.is_open() {
+            continue;
+        }
+        // touchpads send pixels, mouse wheels send lines
+        let distance = match mouse_wheel_event.unit {
+            MouseScrollUnit::Line => mouse_wheel_event.y * LINE_HEIGHT,
+            MouseScrollUnit::Pixel => mouse_wheel_event.y,
+        };
+        offset -= distance; // in reverse because it feels better
+    }
+
+    // keys go to the text box while typing, not the grid
+    let typing = input_query.iter().any(|input| input.focused);
+    if !dialog_data.is_open() && !typing {
+        let page = windows
+            .get_primary()
+            .map(|window| window.height() * 0.8)
+            .unwrap_or(LINE_HEIGHT * 10.0);
+        if keys.just_pressed(KeyCode::PageDown) {
+            offset += page;
+        }
+        if keys.just_pressed(KeyCode::PageUp) {
+            offset -= page;
+        }
+        if keys.just_pressed(KeyCode::Home) {
+            offset = 0.0;
+        }
+        if keys.just_pressed(KeyCode::End) {
+            offset = scroll.max;
+        }
+    }
+
+    scroll_to(&mut scroll, offset, &mut query);
+}
+
+fn drag_scrollbar(
+    mut scroll: ResMut<BackmapScroll>,
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut thumb_query: Query<(&Interaction, &mut ScrollThumb, &Node)>,
+    track_query: Query<&Node, With<Scrollbar>>,
+    mut query: Query<&mut Style, With<Scrollable>>,
+) {
+    let cursor = match windows
+        .get_primary()
+        .and_then(|window| window.cursor_position())
+    {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    let track = match track_query.iter().next() {
+        Some(track) => track.size.y,
+        None => return,
+    };
+
+    for (interaction, mut thumb, node) in thumb_query.iter_mut() {
+        if *interaction == Interaction::Clicked && thumb.drag.is_none() {
+            thumb.drag = Some((cursor.y, scroll.offset));
+        }
+        if !mouse.pressed(MouseButton::Left) {
+            thumb.drag = None;
+        }
+
+        if let Some((start_y, start_offset)) = thumb.drag {
+            // the thumb follows the cursor, the cursor's y goes up from the bottom of the window
+            let room = (track - node.size.y).max(1.0);
+            let offset = start_offset + (start_y - cursor.y) * scroll.max / room;
+            scroll_to(&mut scroll, offset, &mut query);
+        }
+    }
+}
+
+// size the thumb to how much of the grid is on screen and move it to the offset
+fn update_scrollbar(
+    scroll: Res<BackmapScroll>,
+    mut track_query: Query<(&Node, &mut Style), (With<Scrollbar>, Without<ScrollThumb>)>,
+    mut thumb_query: Query<&mut Style, With<ScrollThumb>>,
+) {
+    for (track, mut track_style) in track_query.iter_mut() {
+        // nothing to scroll, no scrollbar
+        let display = match scroll.max > 0.0 {
+            true => Display::Flex,
+            false => Display::None,
+        };
+        if track_style.display != display {
+            track_style.display = display;
+        }
+
+        let visible = track.size.y / (track.size.y + scroll.max);
+        let thumb = (track.size.y * visible).max(SCROLLBAR_WIDTH * 2.0);
+        let top = match scroll.max > 0.0 {
+            true => scroll.offset / scroll.max * (track.size.y - thumb),
+            false => 0.0,
+        };
+
+        for mut style in thumb_query.iter_mut() {
+            // only touch the style when something moved, changing it makes the ui recalculate
+            if style.size.height != Val::Px(thumb) || style.position.top != Val::Px(top) {
+                style.size.height = Val::Px(thumb);
+                style.position.top = Val::Px(top);
+            }
+        }
+    }
+}
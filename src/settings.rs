@@ -4,23 +4,42 @@ use bevy::prelude::*;
 use configparser::ini::Ini;
 
 use crate::{
+    animation::AnimationSettings,
+    announce::AnnounceSettings,
     button_input::{handle_ui_buttons, spawn_button, spawn_main_text, LastMenu},
-    constants::*,
+    fonts::{font_files, UiFont, TEXT_SCALES},
+    handle_json::ThemesJson,
+    indicators::IndicatorSettings,
+    localization::Locale,
+    sound::{SoundSettings, Sounds, VolumeChannel},
     spawn_button_grid,
     states_and_ui::{close_menu, GameState, MenuData, MenuItems},
+    theme::Theme,
 };
 
 pub enum SettingsSubmenus {
     None,
     UI,
     Layout,
+    Sound,
+    Accessibility,
 }
 
 #[derive(Clone, Copy)]
 pub enum SettingsItems {
-    SubItem,
+    Theme,
+    Font,
+    TextSize,
+    Language,
     Layout,
     UI,
+    Animations,
+    Sound,
+    Volume(VolumeChannel),
+    Music,
+    Accessibility,
+    Indicators,
+    Announcements,
 }
 
 pub struct SettingsPage {
@@ -31,15 +50,7 @@ pub struct SettingsPlugin;
 
 impl Plugin for SettingsPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Colors {
-            normal_button: NORMAL_BUTTON,
-            hovered_button: HOVERED_BUTTON,
-            pressed_button: PRESSED_BUTTON,
-            disabled_button: DISABLED_BUTTON,
-            disabled_deck: DISABLED_DECK,
-            enabled_deck: ENABLED_DECK,
-        })
-        .insert_resource(Settings {
+        app.insert_resource(Settings {
             settings: Ini::new(),
         })
         .insert_resource(LayoutSettings { vertical: false })
@@ -50,13 +61,16 @@ impl Plugin for SettingsPlugin {
         .add_system_set(
             SystemSet::on_update(GameState::Settings)
                 .with_system(handle_ui_buttons)
-                .with_system(handle_settings_input),
+                .with_system(handle_settings_input)
+                .with_system(update_settings_labels),
         )
         .add_system_set(SystemSet::on_exit(GameState::Settings).with_system(close_menu))
+        .add_system_set(SystemSet::on_enter(GameState::SettingsSubmenu).with_system(setup_submenu))
         .add_system_set(
             SystemSet::on_update(GameState::SettingsSubmenu)
                 .with_system(handle_ui_buttons)
-                .with_system(handle_settings_input),
+                .with_system(handle_settings_input)
+                .with_system(update_settings_labels),
         )
         .add_system_set(SystemSet::on_exit(GameState::SettingsSubmenu).with_system(close_menu));
     }
@@ -81,6 +95,8 @@ pub struct Colors {
 
     pub disabled_deck: Color,
     pub enabled_deck: Color,
+    pub in_progress_deck: Color,
+    pub locked_deck: Color,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -109,25 +125,38 @@ pub struct Settings {
 pub fn setup_settings(
     mut last_menu: ResMut<LastMenu>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
     mut menu_data: ResMut<MenuData>,
 ) {
     last_menu.last = GameState::MainMenu;
 
-    let font: Handle<Font> = asset_server.load("fonts/Roboto.ttf");
-
     menu_data.button_entity.push(spawn_main_text(
         &mut commands,
-        "Settings",
-        font.clone(),
+        &locale.get("settings.title"),
+        &theme,
         50.0,
     ));
 
     for entity in spawn_button_grid!(
         &mut commands,
-        font.clone(),
-        (MenuItems::SettingsItems(SettingsItems::UI), "UI"),
-        (MenuItems::SettingsItems(SettingsItems::Layout), "layout")
+        &theme,
+        (
+            MenuItems::SettingsItems(SettingsItems::UI),
+            &locale.get("settings.ui")
+        ),
+        (
+            MenuItems::SettingsItems(SettingsItems::Layout),
+            &locale.get("settings.layout")
+        ),
+        (
+            MenuItems::SettingsItems(SettingsItems::Sound),
+            &locale.get("settings.sound")
+        ),
+        (
+            MenuItems::SettingsItems(SettingsItems::Accessibility),
+            &locale.get("settings.accessibility")
+        )
     ) {
         menu_data.button_entity.push(entity);
     }
@@ -137,6 +166,16 @@ fn handle_settings_input(
     mut last_menu: ResMut<LastMenu>,
     mut settings_page: ResMut<SettingsPage>,
     mut state: ResMut<State<GameState>>,
+    mut settings: ResMut<Settings>,
+    mut animations: ResMut<AnimationSettings>,
+    mut sound_settings: ResMut<SoundSettings>,
+    mut indicators: ResMut<IndicatorSettings>,
+    mut announce_settings: ResMut<AnnounceSettings>,
+    mut theme: ResMut<Theme>,
+    themes: Res<ThemesJson>,
+    mut ui_font: ResMut<UiFont>,
+    mut locale: ResMut<Locale>,
+    asset_server: Res<AssetServer>,
     query: Query<(&Interaction, &MenuItems), (With<Button>, Changed<Interaction>)>,
 ) {
     for (interaction, menu_item) in query.iter() {
@@ -147,36 +186,251 @@ fn handle_settings_input(
                     settings_page.page = SettingsSubmenus::UI;
                     state.set(GameState::SettingsSubmenu).unwrap();
                 }
+                MenuItems::SettingsItems(SettingsItems::Animations) => {
+                    animations.enabled = !animations.enabled;
+                    settings.settings.set(
+                        "Animations",
+                        "enabled",
+                        Some(animations.enabled.to_string()),
+                    );
+                    settings.update();
+                }
+                MenuItems::SettingsItems(SettingsItems::Theme) => {
+                    // go to the next theme in the themes folder
+                    if themes.themes.is_empty() {
+                        continue;
+                    }
+                    let next = match themes.themes.iter().position(|x| x.name == theme.name) {
+                        Some(i) => (i + 1) % themes.themes.len(),
+                        None => 0,
+                    };
+                    *theme = Theme::from_json(&themes.themes[next], &asset_server);
+                    settings
+                        .settings
+                        .set("Theme", "name", Some(theme.name.clone()));
+                    settings.update();
+                }
+                MenuItems::SettingsItems(SettingsItems::Font) => {
+                    // the theme's font, then every font in assets/fonts
+                    let mut files: Vec<Option<String>> = vec![None];
+                    files.extend(font_files().into_iter().map(Some));
+
+                    let next = match files.iter().position(|x| *x == ui_font.file) {
+                        Some(i) => (i + 1) % files.len(),
+                        None => 0,
+                    };
+                    ui_font.set_file(files[next].clone(), &theme, &asset_server);
+                    ui_font.save(&mut settings);
+                }
+                MenuItems::SettingsItems(SettingsItems::TextSize) => {
+                    let next = match TEXT_SCALES.iter().position(|x| *x >= ui_font.text_scale) {
+                        Some(i) if TEXT_SCALES[i] > ui_font.text_scale => i,
+                        Some(i) => (i + 1) % TEXT_SCALES.len(),
+                        None => 0,
+                    };
+                    ui_font.text_scale = TEXT_SCALES[next];
+                    ui_font.save(&mut settings);
+                }
+                MenuItems::SettingsItems(SettingsItems::Language) => {
+                    let languages = Locale::languages();
+                    if languages.is_empty() {
+                        continue;
+                    }
+                    let next = match languages.iter().position(|x| *x == locale.language) {
+                        Some(i) => (i + 1) % languages.len(),
+                        None => 0,
+                    };
+                    locale.load(&languages[next]);
+                    settings
+                        .settings
+                        .set("Language", "language", Some(locale.language.clone()));
+                    settings.update();
+                }
+                MenuItems::SettingsItems(SettingsItems::Sound) => {
+                    last_menu.last = GameState::Settings;
+                    settings_page.page = SettingsSubmenus::Sound;
+                    state.set(GameState::SettingsSubmenu).unwrap();
+                }
+                MenuItems::SettingsItems(SettingsItems::Volume(channel)) => {
+                    // goes up in steps of 10 and wraps back around to muted
+                    let volume = sound_settings.volume_mut(*channel);
+                    *volume = match *volume >= 100 {
+                        true => 0,
+                        false => (*volume / 10 + 1) * 10,
+                    };
+                    sound_settings.save(&mut settings);
+                }
+                MenuItems::SettingsItems(SettingsItems::Music) => {
+                    sound_settings.play_music = !sound_settings.play_music;
+                    sound_settings.save(&mut settings);
+                }
+                MenuItems::SettingsItems(SettingsItems::Accessibility) => {
+                    last_menu.last = GameState::Settings;
+                    settings_page.page = SettingsSubmenus::Accessibility;
+                    state.set(GameState::SettingsSubmenu).unwrap();
+                }
+                MenuItems::SettingsItems(SettingsItems::Indicators) => {
+                    indicators.enabled = !indicators.enabled;
+                    settings.settings.set(
+                        "Accessibility",
+                        "deck_indicators",
+                        Some(indicators.enabled.to_string()),
+                    );
+                    settings.update();
+                }
+                MenuItems::SettingsItems(SettingsItems::Announcements) => {
+                    announce_settings.enabled = !announce_settings.enabled;
+                    announce_settings.save(&mut settings);
+                }
                 _ => {}
             }
         }
     }
 }
 
+fn update_settings_labels(
+    animations: Res<AnimationSettings>,
+    sound_settings: Res<SoundSettings>,
+    indicators: Res<IndicatorSettings>,
+    announce_settings: Res<AnnounceSettings>,
+    theme: Res<Theme>,
+    ui_font: Res<UiFont>,
+    locale: Res<Locale>,
+    query: Query<(&MenuItems, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (menu_item, children) in query.iter() {
+        let label = match menu_item {
+            MenuItems::SettingsItems(SettingsItems::Animations) => match animations.enabled {
+                true => locale.get("settings.animations_on"),
+                false => locale.get("settings.animations_off"),
+            },
+            MenuItems::SettingsItems(SettingsItems::Theme) => {
+                locale.format("settings.theme", &[&theme.name])
+            }
+            MenuItems::SettingsItems(SettingsItems::Font) => match ui_font.file_name() {
+                Some(name) => locale.format("settings.font", &[&name]),
+                None => locale.format("settings.font", &[&locale.get("settings.font_theme")]),
+            },
+            MenuItems::SettingsItems(SettingsItems::TextSize) => locale.format(
+                "settings.text_size",
+                &[&(ui_font.text_scale * 100.0).round()],
+            ),
+            MenuItems::SettingsItems(SettingsItems::Language) => {
+                locale.format("settings.language", &[&locale.get("language.name")])
+            }
+            MenuItems::SettingsItems(SettingsItems::Volume(channel)) => locale.format(
+                &format!("settings.{}", channel.key()),
+                &[&sound_settings.volume(*channel)],
+            ),
+            MenuItems::SettingsItems(SettingsItems::Music) => match sound_settings.play_music {
+                true => locale.get("settings.music_on"),
+                false => locale.get("settings.music_off"),
+            },
+            MenuItems::SettingsItems(SettingsItems::Indicators) => match indicators.enabled {
+                true => locale.get("settings.indicators_on"),
+                false => locale.get("settings.indicators_off"),
+            },
+            MenuItems::SettingsItems(SettingsItems::Announcements) => {
+                match announce_settings.enabled {
+                    true => locale.get("settings.announcements_on"),
+                    false => locale.get("settings.announcements_off"),
+                }
+            }
+            _ => continue,
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
+
 pub fn setup_submenu(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
     submenu: Res<SettingsPage>,
+    sounds: Res<Sounds>,
     mut menu_data: ResMut<MenuData>,
     mut last_menu: ResMut<LastMenu>,
-    mut egui_context: ResMut<EguiContext>,
 ) {
-    egui::Window::new("Hello").show(egui_context.ctx_mut(), |ui| {
-        ui.label("world");
-    });
-
     match submenu.page {
         SettingsSubmenus::UI => {
-            let font = asset_server.load("fonts/Roboto.ttf");
             for entity in spawn_button_grid!(
                 &mut commands,
-                font,
-                (MenuItems::SettingsItems(SettingsItems::SubItem), "Color")
+                &theme,
+                (MenuItems::SettingsItems(SettingsItems::Theme), ""),
+                (MenuItems::SettingsItems(SettingsItems::Font), ""),
+                (MenuItems::SettingsItems(SettingsItems::TextSize), ""),
+                (MenuItems::SettingsItems(SettingsItems::Language), "")
             ) {
                 menu_data.button_entity.push(entity);
             }
             last_menu.last = GameState::Settings; // TODO add a way to backtrack in the submenu, as easy as adding a special case into handle_button_inputs but im lazy
         }
+        SettingsSubmenus::Sound => {
+            menu_data.button_entity.push(spawn_main_text(
+                &mut commands,
+                &locale.get("settings.sound"),
+                &theme,
+                50.0,
+            ));
+            for entity in spawn_button_grid!(
+                &mut commands,
+                &theme,
+                (
+                    MenuItems::SettingsItems(SettingsItems::Volume(VolumeChannel::Master)),
+                    ""
+                ),
+                (
+                    MenuItems::SettingsItems(SettingsItems::Volume(VolumeChannel::Effects)),
+                    ""
+                ),
+                (
+                    MenuItems::SettingsItems(SettingsItems::Volume(VolumeChannel::Music)),
+                    ""
+                )
+            ) {
+                menu_data.button_entity.push(entity);
+            }
+            // nothing to turn on without a music file, so only show the toggle when there is one
+            if sounds.has_music() {
+                menu_data.button_entity.push(spawn_button(
+                    &mut commands,
+                    &theme,
+                    "",
+                    theme.button_font_size,
+                    820.0,
+                    100.0, // below the volume buttons
+                    theme.button_size,
+                    MenuItems::SettingsItems(SettingsItems::Music),
+                ));
+            }
+            last_menu.last = GameState::Settings;
+        }
+        SettingsSubmenus::Accessibility => {
+            menu_data.button_entity.push(spawn_main_text(
+                &mut commands,
+                &locale.get("settings.accessibility"),
+                &theme,
+                50.0,
+            ));
+            for entity in spawn_button_grid!(
+                &mut commands,
+                &theme,
+                (MenuItems::SettingsItems(SettingsItems::Animations), ""),
+                (MenuItems::SettingsItems(SettingsItems::Indicators), ""),
+                (MenuItems::SettingsItems(SettingsItems::Announcements), "")
+            ) {
+                menu_data.button_entity.push(entity);
+            }
+            last_menu.last = GameState::Settings;
+        }
         _ => {}
     }
 }
@@ -0,0 +1,262 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_kira_audio::{Audio, AudioChannel, AudioPlugin, AudioSource};
+
+use crate::{settings::Settings, states_and_ui::DeckNumber};
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(AudioPlugin)
+            .add_event::<SoundEvent>()
+            .insert_resource(SoundSettings::default())
+            .insert_resource(SoundChannels {
+                effects: AudioChannel::new("effects".to_owned()),
+                music: AudioChannel::new("music".to_owned()),
+            })
+            .insert_resource(Sounds::default())
+            .add_startup_system_to_stage(StartupStage::PostStartup, load_sounds)
+            .add_system(button_sounds)
+            .add_system(play_sounds)
+            .add_system(apply_sound_settings);
+    }
+}
+
+// every sound the game can make, the files can be changed in the [Sounds] section of settings.ini
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    Hover,
+    Click,
+    SelectDeck,
+    Flip,
+    CompleteDeck,
+    CompleteRun,
+}
+
+impl SoundEffect {
+    const ALL: [SoundEffect; 6] = [
+        SoundEffect::Hover,
+        SoundEffect::Click,
+        SoundEffect::SelectDeck,
+        SoundEffect::Flip,
+        SoundEffect::CompleteDeck,
+        SoundEffect::CompleteRun,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            SoundEffect::Hover => "hover",
+            SoundEffect::Click => "click",
+            SoundEffect::SelectDeck => "select_deck",
+            SoundEffect::Flip => "flip",
+            SoundEffect::CompleteDeck => "complete_deck",
+            SoundEffect::CompleteRun => "complete_run",
+        }
+    }
+}
+
+// send one of these to play a sound effect
+pub struct SoundEvent(pub SoundEffect);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VolumeChannel {
+    Master,
+    Effects,
+    Music,
+}
+
+impl VolumeChannel {
+    pub fn key(self) -> &'static str {
+        match self {
+            VolumeChannel::Master => "master_volume",
+            VolumeChannel::Effects => "effects_volume",
+            VolumeChannel::Music => "music_volume",
+        }
+    }
+}
+
+// stored in the [Sound] section of settings.ini, volumes go from 0 to 100
+#[derive(Debug, Clone)]
+pub struct SoundSettings {
+    pub master: u64,
+    pub effects: u64,
+    pub music: u64,
+    pub play_music: bool,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        SoundSettings {
+            master: 80,
+            effects: 80,
+            music: 50,
+            play_music: true,
+        }
+    }
+}
+
+impl SoundSettings {
+    pub fn load(&mut self, settings: &Settings) {
+        for channel in [
+            VolumeChannel::Master,
+            VolumeChannel::Effects,
+            VolumeChannel::Music,
+        ] {
+            if let Ok(Some(volume)) = settings.settings.getuint("Sound", channel.key()) {
+                *self.volume_mut(channel) = volume.min(100);
+            }
+        }
+        if let Ok(Some(play_music)) = settings.settings.getbool("Sound", "music") {
+            self.play_music = play_music;
+        }
+    }
+
+    pub fn save(&self, settings: &mut Settings) {
+        for channel in [
+            VolumeChannel::Master,
+            VolumeChannel::Effects,
+            VolumeChannel::Music,
+        ] {
+            settings.settings.set(
+                "Sound",
+                channel.key(),
+                Some(self.volume(channel).to_string()),
+            );
+        }
+        settings
+            .settings
+            .set("Sound", "music", Some(self.play_music.to_string()));
+        settings.update();
+    }
+
+    pub fn volume(&self, channel: VolumeChannel) -> u64 {
+        match channel {
+            VolumeChannel::Master => self.master,
+            VolumeChannel::Effects => self.effects,
+            VolumeChannel::Music => self.music,
+        }
+    }
+
+    pub fn volume_mut(&mut self, channel: VolumeChannel) -> &mut u64 {
+        match channel {
+            VolumeChannel::Master => &mut self.master,
+            VolumeChannel::Effects => &mut self.effects,
+            VolumeChannel::Music => &mut self.music,
+        }
+    }
+
+    // the volume a channel actually plays at, after the master volume
+    fn output(&self, channel: VolumeChannel) -> f32 {
+        (self.master as f32 / 100.0) * (self.volume(channel) as f32 / 100.0)
+    }
+}
+
+pub struct SoundChannels {
+    effects: AudioChannel,
+    music: AudioChannel,
+}
+
+// the loaded sound files, sounds whose file is missing just dont play
+#[derive(Default)]
+pub struct Sounds {
+    effects: Vec<(SoundEffect, Handle<AudioSource>)>,
+    music: Option<Handle<AudioSource>>,
+    music_playing: bool,
+}
+
+impl Sounds {
+    pub fn has_music(&self) -> bool {
+        self.music.is_some()
+    }
+}
+
+// the file for a sound, relative to the assets folder. an empty file turns the sound off
+fn sound_file(settings: &Settings, key: &str, default: &str) -> Option<String> {
+    let file = settings
+        .settings
+        .get("Sounds", key)
+        .unwrap_or_else(|| default.to_owned());
+
+    match !file.is_empty() && Path::new("assets").join(&file).exists() {
+        true => Some(file),
+        false => None,
+    }
+}
+
+// runs after setup_game so settings.ini has been read
+fn load_sounds(
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    mut sounds: ResMut<Sounds>,
+) {
+    for effect in SoundEffect::ALL {
+        let default = format!("sounds/{}.wav", effect.key());
+        if let Some(file) = sound_file(&settings, effect.key(), &default) {
+            sounds
+                .effects
+                .push((effect, asset_server.load(file.as_str())));
+        }
+    }
+    // no track ships with the game, so there's only music when one is set in settings.ini
+    sounds.music = sound_file(&settings, "music", "").map(|file| asset_server.load(file.as_str()));
+}
+
+// every button makes a sound when hovered or clicked, decks get their own
+fn button_sounds(
+    mut sound_events: EventWriter<SoundEvent>,
+    query: Query<(&Interaction, Option<&DeckNumber>), (With<Button>, Changed<Interaction>)>,
+) {
+    for (interaction, deck_num) in query.iter() {
+        match (interaction, deck_num) {
+            (Interaction::Clicked, Some(_)) => {
+                sound_events.send(SoundEvent(SoundEffect::SelectDeck))
+            }
+            (Interaction::Clicked, None) => sound_events.send(SoundEvent(SoundEffect::Click)),
+            (Interaction::Hovered, _) => sound_events.send(SoundEvent(SoundEffect::Hover)),
+            _ => {}
+        }
+    }
+}
+
+fn play_sounds(
+    audio: Res<Audio>,
+    channels: Res<SoundChannels>,
+    sounds: Res<Sounds>,
+    mut sound_events: EventReader<SoundEvent>,
+) {
+    for SoundEvent(effect) in sound_events.iter() {
+        if let Some((_, handle)) = sounds.effects.iter().find(|(x, _)| x == effect) {
+            audio.play_in_channel(handle.clone(), &channels.effects);
+        }
+    }
+}
+
+// keep the channel volumes and the music in sync with the settings
+fn apply_sound_settings(
+    audio: Res<Audio>,
+    channels: Res<SoundChannels>,
+    sound_settings: Res<SoundSettings>,
+    mut sounds: ResMut<Sounds>,
+) {
+    if !sound_settings.is_changed() {
+        return;
+    }
+
+    audio.set_volume_in_channel(
+        sound_settings.output(VolumeChannel::Effects),
+        &channels.effects,
+    );
+    audio.set_volume_in_channel(sound_settings.output(VolumeChannel::Music), &channels.music);
+
+    if let Some(music) = sounds.music.clone() {
+        if sound_settings.play_music && !sounds.music_playing {
+            audio.play_looped_in_channel(music, &channels.music);
+            sounds.music_playing = true;
+        } else if !sound_settings.play_music && sounds.music_playing {
+            audio.stop_channel(&channels.music);
+            sounds.music_playing = false;
+        }
+    }
+}
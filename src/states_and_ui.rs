@@ -1,10 +1,18 @@
 use crate::{
-    actual_game::{setup_actual_game, update_score},
+    actual_game::{check_run_finished, setup_actual_game, update_board, update_score},
+    board_camera::CameraItems,
+    bulk_select::BulkItems,
     button_input::*,
     constants::{GameGlobals, NUM_COLLUMNS},
     deck::{make_decks, DeckBacks},
+    deck_filter::FilterItems,
     handle_json::*,
-    settings::{Colors, SettingsItems},
+    localization::Locale,
+    presets::PresetItems,
+    randomize::{DeckLocks, RandomizeItems},
+    scroll::BackmapScroll,
+    settings::SettingsItems,
+    theme::Theme,
 };
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
@@ -18,6 +26,7 @@ impl Plugin for MenuPlugin {
             // used for keeping track of text/buttons so they can be despawned
             button_entity: Vec::new(),
         })
+        .insert_resource(DialogData { entity: None })
         // MainMenu
         .add_system_set(
             SystemSet::on_enter(GameState::MainMenu)
@@ -35,7 +44,8 @@ impl Plugin for MenuPlugin {
         .add_system_set(
             SystemSet::on_update(GameState::PreGame)
                 .with_system(update_pre_game_text)
-                .with_system(scroll_backmap)
+                .with_system(update_pre_game_colors)
+                .with_system(handle_undo_keys)
                 .with_system(handle_ui_buttons)
                 .with_system(handle_choosing_cards),
         )
@@ -48,21 +58,24 @@ impl Plugin for MenuPlugin {
         .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_actual_game))
         .add_system_set(
             SystemSet::on_update(GameState::InGame)
-                .with_system(scroll_gamemap)
                 .with_system(update_score)
-                .with_system(handle_ingame_input),
+                .with_system(update_board)
+                .with_system(check_run_finished)
+                .with_system(handle_ui_buttons.after("run_export"))
+                .with_system(handle_undo_keys)
+                .with_system(
+                    handle_ingame_input
+                        .label("ingame_input")
+                        .after("board_drag"),
+                ),
         )
+        .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(close_menu))
         // DeckSelection
-        .add_system_set(
-            SystemSet::on_update(GameState::DeckSelection).with_system(handle_choosing_cards),
-        )
         .add_system_set(SystemSet::on_enter(GameState::DeckSelection).with_system(setup_deck_menu))
-        .add_system_set(
-            SystemSet::on_update(GameState::DeckSelection).with_system(handle_choosing_cards),
-        )
         .add_system_set(
             SystemSet::on_update(GameState::DeckSelection)
-                .with_system(scroll_backmap)
+                .with_system(handle_choosing_cards)
+                .with_system(update_deck_selection_colors)
                 .with_system(handle_ui_buttons),
         )
         .add_system_set(SystemSet::on_exit(GameState::DeckSelection).with_system(close_menu));
@@ -82,6 +95,7 @@ pub enum GameState {
     Left,
     Right,
     Loading,
+    Stats,
 }
 #[derive(Component)]
 pub struct MainMenu;
@@ -99,9 +113,21 @@ pub enum MenuItems {
     NewGame,
     DeckSelection,
     Save,
+    Filter(FilterItems),
+    Randomize,
+    Randomizer(RandomizeItems),
+    Preset(PresetItems),
+    Bulk(BulkItems),
+    Camera(CameraItems),
+    Undo,
+    Redo,
+    ExportSplits,
+    ExportRun,
+    Dialog(DialogItems),
     Left,
     Right,
     HowToPlay,
+    Stats,
     Quit,
 }
 // stores what button corresponds to what deck in DeckSelection
@@ -114,6 +140,36 @@ pub struct MenuData {
     pub button_entity: Vec<Entity>,
 }
 
+// buttons that can appear inside of a dialog
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DialogItems {
+    SaveAndQuit,
+    QuitWithoutSaving,
+    Overwrite,
+    FinishRun,
+    // exported in handle_export_buttons, then finished like FinishRun
+    ExportAndFinish,
+    SaveDecks,    // save the enabled decks, then leave deck selection
+    DiscardDecks, // throw away changes to the enabled decks, then leave deck selection
+    Cancel,
+}
+
+// keeps track of the currently open dialog, only one can be open at a time
+pub struct DialogData {
+    pub entity: Option<Entity>,
+}
+
+impl DialogData {
+    pub fn is_open(&self) -> bool {
+        self.entity.is_some()
+    }
+}
+
+#[derive(Component)]
+pub struct Dialog {
+    // marks the root node of a dialog so everything inside it gets despawned together
+}
+
 #[derive(Component)]
 struct ArrowTracker {
     // only used to track the arrow entity for deltion
@@ -146,25 +202,26 @@ fn setup_main_menu(
     asset_server: Res<AssetServer>,
     mut menu_data: ResMut<MenuData>,
     mut enabled_json: ResMut<EnabledJson>,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
 ) {
     enabled_json.load(); // load saved enabled decks
 
-    let font: Handle<Font> = asset_server.load("fonts/Roboto.ttf");
-
     menu_data.button_entity.push(spawn_main_text(
         &mut commands,
-        "Main Menu",
-        font.clone(),
+        &locale.get("menu.main_menu"),
+        &theme,
         5.0,
     ));
     // spawn buttons and add them to the button entity vector to despawn later
     for i in spawn_button_grid!(
         &mut commands,
-        font.clone(),
-        (MenuItems::Continue, "Continue"),
-        (MenuItems::NewGame, "New Game"),
-        (MenuItems::DeckSelection, "Deck Select"),
-        (MenuItems::HowToPlay, "How To Play")
+        &theme,
+        (MenuItems::Continue, &locale.get("menu.continue")),
+        (MenuItems::NewGame, &locale.get("menu.new_game")),
+        (MenuItems::DeckSelection, &locale.get("menu.deck_select")),
+        (MenuItems::Stats, &locale.get("menu.stats")),
+        (MenuItems::HowToPlay, &locale.get("menu.how_to_play"))
     ) {
         menu_data.button_entity.push(i);
     }
@@ -181,25 +238,33 @@ fn setup_main_menu(
 
 fn setup_deck_menu(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     mut menu_data: ResMut<MenuData>,
+    mut scroll: ResMut<BackmapScroll>,
     globals: ResMut<GameGlobals>,
     enabled_json: Res<EnabledJson>,
     deck_backs: Res<DeckBacks>,
-    colors: Res<Colors>,
+    deck_data: Res<DeckDataWrapper>,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
 ) {
-    let font: Handle<Font> = asset_server.load("fonts/Roboto.ttf");
     let size = Vec2::new(250.0, 100.0);
-    let text = spawn_main_text(&mut commands, "Deck Selection", font.clone(), -70.0);
+    scroll.reset(globals.total_decks);
+
+    let text = spawn_main_text(
+        &mut commands,
+        &locale.get("deck_selection.title"),
+        &theme,
+        -70.0,
+    );
 
     commands.entity(text).insert(Scrollable {}); // make this scroll with the cards. looks super weird otherwise
     menu_data.button_entity.push(text);
 
     menu_data.button_entity.push(spawn_button(
         &mut commands,
-        font.clone(),
-        "Save",
-        40.0,
+        &theme,
+        &locale.get("button.save"),
+        theme.button_font_size,
         0.0, // right between the cards
         0.0,
         size,
@@ -212,13 +277,19 @@ fn setup_deck_menu(
         let mut color = UiColor::default();
 
         if enabled_json.check_disabled(&i) {
-            color = colors.disabled_deck.into();
+            color = theme.colors.disabled_deck.into();
         } else {
         }
         let back = deck_backs.backs.get(i).unwrap();
-        menu_data
-            .button_entity
-            .push(spawn_back_grid(&mut commands, back.clone(), i, i, color));
+        menu_data.button_entity.push(spawn_back_grid(
+            &mut commands,
+            back.clone(),
+            &theme,
+            deck_data.decks[i].display_name(&locale.language),
+            i,
+            i,
+            color,
+        ));
     }
 }
 
@@ -237,19 +308,17 @@ fn setup_pre_game(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut menu_data: ResMut<MenuData>,
+    mut scroll: ResMut<BackmapScroll>,
     enabled_json: Res<EnabledJson>,
     deck_backs: Res<DeckBacks>,
+    deck_data: Res<DeckDataWrapper>,
     current_run_json: Res<CurrentRunJson>,
-    colors: Res<Colors>,
+    globals: Res<GameGlobals>,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
 ) {
-    let font = asset_server.load("fonts/Roboto.ttf");
-
-    let text = spawn_main_text(
-        &mut commands,
-        format!("Select {} more decks!", enabled_json.enabled.len()).as_str(),
-        font.clone(),
-        -120.0,
-    );
+    let (status, _) = pre_game_status(&enabled_json, &current_run_json, &globals, &locale);
+    let text = spawn_main_text(&mut commands, &status, &theme, -120.0);
 
     menu_data.button_entity.push(
         commands
@@ -259,29 +328,50 @@ fn setup_pre_game(
             .id(),
     ); // make this scroll with the cards. looks super weird otherwise. also make it trackable for updating in the future
 
+    let play = spawn_button_img(
+        &mut commands,
+        Vec2::new(100.0, 100.0),
+        40.0,
+        0.0,
+        MenuItems::Play,
+        asset_server.load("ui/green_checkmark.png"),
+    );
+    commands.entity(play).insert(PlayButton {});
+    menu_data.button_entity.push(play);
+
+    // decks from a saved run that have been disabled since still need to show up, so they can be removed
+    let mut shown = enabled_json.enabled.clone();
+    for deck in current_run_json.decks.iter() {
+        if !shown.contains(deck) {
+            shown.push(*deck);
+        }
+    }
+    scroll.reset(shown.len());
+
     let mut i = 0;
-    for j in enabled_json.enabled.iter() {
+    for j in shown.iter() {
         let mut color = Default::default();
 
         if current_run_json.check_deck(j) {
-            color = colors.enabled_deck.into();
+            color = theme.colors.enabled_deck.into();
         }
         let back = deck_backs.backs.get(*j).unwrap();
 
-        menu_data
-            .button_entity
-            .push(spawn_back_grid(&mut commands, back.clone(), i, *j, color));
+        menu_data.button_entity.push(spawn_back_grid(
+            &mut commands,
+            back.clone(),
+            &theme,
+            deck_data.decks[*j].display_name(&locale.language),
+            i,
+            *j,
+            color,
+        ));
         i += 1;
     }
 }
 
-pub fn spawn_back_grid(
-    commands: &mut Commands,
-    image: Handle<Image>,
-    index: usize,
-    deck_num: usize,
-    color: UiColor,
-) -> Entity {
+// where the back at this index of the grid goes, as (left, bottom)
+pub fn grid_position(index: usize) -> Vec2 {
     // start position
     let start_y = 1350.0;
     let start_x = -30.0;
@@ -303,6 +393,20 @@ pub fn spawn_back_grid(
         }
     }
 
+    Vec2::new(start_x + (card_x * mulx), start_y - (card_y * muly))
+}
+
+pub fn spawn_back_grid(
+    commands: &mut Commands,
+    image: Handle<Image>,
+    theme: &Theme,
+    name: &str,
+    index: usize,
+    deck_num: usize,
+    color: UiColor,
+) -> Entity {
+    let position = grid_position(index);
+
     let image = UiImage::from(image);
     commands
         .spawn_bundle(ButtonBundle {
@@ -310,8 +414,8 @@ pub fn spawn_back_grid(
                 size: Size::new(Val::Px(210.0), Val::Px(300.0)),
                 position_type: PositionType::Absolute,
                 position: Rect {
-                    bottom: Val::Px(start_y - (card_y * muly)),
-                    left: Val::Px(start_x + (card_x * mulx)),
+                    bottom: Val::Px(position.y),
+                    left: Val::Px(position.x),
                     ..Default::default()
                 },
                 justify_content: JustifyContent::Center,
@@ -322,6 +426,33 @@ pub fn spawn_back_grid(
             color,
             ..Default::default()
         })
+        .with_children(|parent| {
+            // the deck's name right under the back
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        bottom: Val::Px(-40.0),
+                        ..Default::default()
+                    },
+                    max_size: Size::new(Val::Px(280.0), Val::Undefined),
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    name,
+                    TextStyle {
+                        font: theme.font.clone(),
+                        font_size: 26.0,
+                        color: theme.text_color,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                ..Default::default()
+            });
+        })
         .insert(DeckNumber { num: deck_num })
         .insert(Scrollable {})
         .id()
@@ -337,34 +468,238 @@ struct PreGameText {
     // track the pre-game text so it can be updated
 }
 
+// gray out the disabled decks, also keeps the colors right after a bulk action or switching presets
+fn update_deck_selection_colors(
+    enabled_json: Res<EnabledJson>,
+    theme: Res<Theme>,
+    mut query: Query<(&DeckNumber, &mut UiColor), With<Button>>,
+) {
+    if !enabled_json.is_changed() {
+        return;
+    }
+
+    for (deck_num, mut color) in query.iter_mut() {
+        *color = match enabled_json.check_disabled(&deck_num.num) {
+            true => theme.colors.disabled_deck.into(),
+            false => Default::default(),
+        };
+    }
+}
+
+// color the decks that are part of the run, also keeps the colors right after an undo
+fn update_pre_game_colors(
+    current_run_json: Res<CurrentRunJson>,
+    locks: Res<DeckLocks>,
+    theme: Res<Theme>,
+    mut query: Query<(&DeckNumber, &mut UiColor), With<Button>>,
+) {
+    if !current_run_json.is_changed() && !locks.is_changed() {
+        return;
+    }
+
+    for (deck_num, mut color) in query.iter_mut() {
+        if locks.decks.contains(&deck_num.num) && current_run_json.check_deck(&deck_num.num) {
+            *color = theme.colors.locked_deck.into();
+        } else if current_run_json.check_deck(&deck_num.num) {
+            *color = theme.colors.enabled_deck.into();
+        } else {
+            *color = Default::default();
+        }
+    }
+}
+
+#[derive(Component)]
+struct PlayButton {
+    // only shown once the selection is valid
+}
+
+// what the pre-game title should say, and whether the run can be started
+fn pre_game_status(
+    enabled_json: &EnabledJson,
+    current_run_json: &CurrentRunJson,
+    globals: &GameGlobals,
+    locale: &Locale,
+) -> (String, bool) {
+    let picked = current_run_json.decks.len();
+    let wanted = globals.decks_per_game;
+
+    if picked == wanted {
+        (locale.get("pregame.ready"), true)
+    } else if picked > wanted {
+        (
+            locale.format("pregame.remove", &[&(picked - wanted)]),
+            false,
+        )
+    } else if enabled_json.enabled.len() < wanted {
+        (
+            locale.format(
+                "pregame.not_enough",
+                &[
+                    &enabled_json.enabled.len(),
+                    &(wanted - enabled_json.enabled.len()),
+                ],
+            ),
+            false,
+        )
+    } else {
+        (
+            locale.format("pregame.select", &[&(wanted - picked)]),
+            false,
+        )
+    }
+}
+
 fn update_pre_game_text(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
     mut query: Query<&mut Text, With<PreGameText>>,
+    mut play_query: Query<&mut Style, With<PlayButton>>,
+    added_query: Query<(), Added<PlayButton>>,
     enabled_json: Res<EnabledJson>,
     current_run_json: Res<CurrentRunJson>,
-    mut menu_data: ResMut<MenuData>,
+    globals: Res<GameGlobals>,
+    locale: Res<Locale>,
 ) {
-    if !current_run_json.is_changed() {
+    if !current_run_json.is_changed()
+        && !globals.is_changed()
+        && !enabled_json.is_changed()
+        && added_query.is_empty()
+    {
         // make sure this only runs when it needs to
         return;
     }
 
+    let (status, valid) = pre_game_status(&enabled_json, &current_run_json, &globals, &locale);
+
     for mut text in query.iter_mut() {
-        text.sections[0].value = format!(
-            "Select {} more decks!",
-            enabled_json.enabled.len() - current_run_json.decks.len()
-        );
+        text.sections[0].value = status.clone();
     }
-    if enabled_json.enabled.len() - current_run_json.decks.len() == 0 {
-        // if done picking cards
-        menu_data.button_entity.push(spawn_button_img(
-            &mut commands,
-            Vec2::new(100.0, 100.0),
-            40.0,
-            0.0,
-            MenuItems::Play,
-            asset_server.load("ui/green_checkmark.png"),
-        ));
+    for mut style in play_query.iter_mut() {
+        // only show the play button once done picking cards
+        style.display = match valid {
+            true => Display::Flex,
+            false => Display::None,
+        };
+    }
+}
+
+// spawns a modal dialog with a title, a body and a row of buttons in the middle of the screen
+pub fn spawn_dialog(
+    commands: &mut Commands,
+    dialog_data: &mut DialogData,
+    theme: &Theme,
+    title: &str,
+    body: &str,
+    buttons: &[(DialogItems, &str)],
+) -> Entity {
+    if let Some(old) = dialog_data.entity.take() {
+        // never stack dialogs on top of each other
+        commands.entity(old).despawn_recursive();
+    }
+
+    let entity = commands
+        .spawn_bundle(NodeBundle {
+            // covers the whole screen and darkens whatever is behind the dialog
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(900.0), Val::Px(450.0)),
+                        flex_direction: FlexDirection::ColumnReverse, // top to bottom
+                        justify_content: JustifyContent::SpaceAround,
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: theme.background.color.into(),
+                    ..Default::default()
+                })
+                .with_children(|panel| {
+                    panel.spawn_bundle(TextBundle {
+                        text: Text::with_section(
+                            title,
+                            TextStyle {
+                                font: theme.font.clone(),
+                                font_size: 60.0,
+                                color: theme.text_color,
+                            },
+                            Default::default(),
+                        ),
+                        ..Default::default()
+                    });
+                    panel.spawn_bundle(TextBundle {
+                        text: Text::with_section(
+                            body,
+                            TextStyle {
+                                font: theme.font.clone(),
+                                font_size: 35.0,
+                                color: theme.text_color,
+                            },
+                            Default::default(),
+                        ),
+                        ..Default::default()
+                    });
+                    panel
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|row| {
+                            for (item, label) in buttons {
+                                row.spawn_bundle(ButtonBundle {
+                                    style: Style {
+                                        size: Size::new(
+                                            Val::Px(theme.button_size.x),
+                                            Val::Px(theme.button_size.y),
+                                        ),
+                                        margin: Rect::all(Val::Px(15.0)),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..Default::default()
+                                    },
+                                    color: theme.colors.normal_button.into(),
+                                    ..Default::default()
+                                })
+                                .with_children(|button| {
+                                    button.spawn_bundle(TextBundle {
+                                        text: Text::with_section(
+                                            *label,
+                                            TextStyle {
+                                                font: theme.font.clone(),
+                                                font_size: theme.button_font_size,
+                                                color: theme.text_color,
+                                            },
+                                            Default::default(),
+                                        ),
+                                        ..Default::default()
+                                    });
+                                })
+                                .insert(MenuItems::Dialog(*item));
+                            }
+                        });
+                });
+        })
+        .insert(Dialog {})
+        .id();
+
+    dialog_data.entity = Some(entity);
+    entity
+}
+
+pub fn close_dialog(commands: &mut Commands, dialog_data: &mut DialogData) {
+    if let Some(entity) = dialog_data.entity.take() {
+        commands.entity(entity).despawn_recursive();
     }
 }
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    button_input::{handle_ui_buttons, spawn_main_text, spawn_text},
+    handle_json::{DeckDataWrapper, RunHistoryJson},
+    localization::Locale,
+    states_and_ui::{close_menu, GameState, MenuData},
+    theme::Theme,
+};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Stats).with_system(setup_stats))
+            .add_system_set(SystemSet::on_update(GameState::Stats).with_system(handle_ui_buttons))
+            .add_system_set(SystemSet::on_exit(GameState::Stats).with_system(close_menu));
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeckStats {
+    pub plays: usize,
+    pub completions: usize,
+    pub last_played: u64, // unix time the last run with this deck finished
+}
+
+impl DeckStats {
+    // percentage of plays where the deck got completed
+    pub fn win_rate(&self) -> f32 {
+        if self.plays == 0 {
+            return 0.0;
+        }
+        self.completions as f32 / self.plays as f32 * 100.0
+    }
+}
+
+// stats for every deck that has been played, keyed by the deck index
+pub fn deck_stats(history: &RunHistoryJson) -> HashMap<usize, DeckStats> {
+    let mut stats: HashMap<usize, DeckStats> = HashMap::new();
+
+    for run in history.runs.iter() {
+        for deck in run.decks.iter() {
+            let entry = stats.entry(deck.deck).or_default();
+            entry.plays += 1;
+            if deck.completed {
+                entry.completions += 1;
+            }
+            entry.last_played = entry.last_played.max(run.finished);
+        }
+    }
+    stats
+}
+
+// how often every drawn challenge was left uncompleted, worst first
+// the key is (deck, primary, card index)
+pub fn failed_challenges(history: &RunHistoryJson) -> Vec<((usize, bool, usize), usize)> {
+    let mut fails: HashMap<(usize, bool, usize), usize> = HashMap::new();
+
+    for run in history.runs.iter() {
+        for deck in run.decks.iter().filter(|deck| !deck.completed) {
+            if let Some(drawn) = deck.drawn {
+                *fails.entry((deck.deck, true, drawn.primary)).or_default() += 1;
+                *fails
+                    .entry((deck.deck, false, drawn.secondary))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    let mut fails: Vec<((usize, bool, usize), usize)> = fails.into_iter().collect();
+    fails.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    fails
+}
+
+// average time between starting and finishing a run, in seconds
+pub fn average_run_length(history: &RunHistoryJson) -> Option<u64> {
+    if history.runs.is_empty() {
+        return None;
+    }
+
+    let total: u64 = history.runs.iter().map(|run| run.length()).sum();
+    Some(total / history.runs.len() as u64)
+}
+
+pub fn format_duration(secs: u64) -> String {
+    format!(
+        "{}h {:02}m {:02}s",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+pub fn card_label(
+    deck_data: &DeckDataWrapper,
+    locale: &Locale,
+    deck: usize,
+    primary: bool,
+    index: usize,
+) -> String {
+    let name = match deck_data.decks.get(deck) {
+        Some(data) => {
+            if let Some(text) = data.card_text(primary, index) {
+                return format!("{}: {}", data.display_name(&locale.language), text);
+            }
+            data.display_name(&locale.language).to_owned()
+        }
+        None => locale.format("stats.unknown_deck", &[&deck]),
+    };
+    let key = match primary {
+        true => "stats.primary_card",
+        false => "stats.secondary_card",
+    };
+    locale.format(key, &[&name, &(index + 1)])
+}
+
+fn setup_stats(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    mut menu_data: ResMut<MenuData>,
+    run_history: Res<RunHistoryJson>,
+    deck_data: Res<DeckDataWrapper>,
+) {
+    menu_data.button_entity.push(spawn_main_text(
+        &mut commands,
+        &locale.get("menu.stats"),
+        &theme,
+        150.0,
+    ));
+
+    // completion rate for every deck, best first
+    let mut decks: Vec<(usize, DeckStats)> = deck_stats(&run_history).into_iter().collect();
+    decks.sort_by(|a, b| {
+        b.1.win_rate()
+            .partial_cmp(&a.1.win_rate())
+            .unwrap()
+            .then(b.1.plays.cmp(&a.1.plays))
+    });
+
+    let mut deck_text = locale.get("stats.win_rate") + "\n\n";
+    for (deck, stats) in decks.iter() {
+        let name = match deck_data.decks.get(*deck) {
+            Some(data) => data.display_name(&locale.language).to_owned(),
+            None => locale.format("stats.unknown_deck", &[deck]),
+        };
+        deck_text += &format!(
+            "{}: {:.0}% ({}/{})\n",
+            name,
+            stats.win_rate(),
+            stats.completions,
+            stats.plays
+        );
+    }
+
+    let mut fail_text = locale.get("stats.most_failed") + "\n\n";
+    for ((deck, primary, index), fails) in failed_challenges(&run_history).iter().take(10) {
+        fail_text += &format!(
+            "{}: {}\n",
+            card_label(&deck_data, &locale, *deck, *primary, *index),
+            fails
+        );
+    }
+
+    let wins = run_history.runs.iter().filter(|run| run.won).count();
+    let mut run_lines = vec![
+        locale.get("stats.runs"),
+        String::new(),
+        locale.format("stats.played", &[&run_history.runs.len()]),
+        locale.format("stats.won", &[&wins]),
+    ];
+    if let Some(average) = average_run_length(&run_history) {
+        run_lines.push(locale.format("stats.average_length", &[&format_duration(average)]));
+    }
+
+    run_lines.push(String::new());
+    run_lines.push(locale.get("stats.personal_bests"));
+    run_lines.push(String::new());
+    if let Some(best) = run_history.runs.iter().map(|run| run.score).max() {
+        run_lines.push(locale.format("stats.highest_score", &[&best]));
+    }
+    if let Some(fastest) = run_history
+        .runs
+        .iter()
+        .filter(|run| run.won)
+        .map(|run| run.length())
+        .min()
+    {
+        run_lines.push(locale.format("stats.fastest_win", &[&format_duration(fastest)]));
+    }
+    let run_text = run_lines.join("\n") + "\n";
+
+    for (text, x) in [(deck_text, 150.0), (fail_text, 750.0), (run_text, 1400.0)] {
+        menu_data
+            .button_entity
+            .push(spawn_text(&mut commands, &text, &theme, 30.0, x, 250.0));
+    }
+}
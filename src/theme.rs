@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+
+use crate::{
+    button_input::TextInput,
+    constants::*,
+    handle_json::{ThemeJson, ThemesJson},
+    settings::{Background, Colors, Settings},
+    states_and_ui::MenuItems,
+    MainCamera,
+};
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Theme::default())
+            .add_system(apply_theme);
+    }
+}
+
+// colours, font and sizes every menu is built with, the font can be overridden by UiFont
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub colors: Colors,
+    pub background: Background,
+    pub text_color: Color,
+    pub font: Handle<Font>,
+    pub title_size: f32,
+    pub button_font_size: f32,
+    pub text_size: f32,
+    pub button_size: Vec2,
+}
+
+// the look the game had before themes, used for anything a theme file leaves out
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: "Default".to_owned(),
+            colors: Colors {
+                normal_button: NORMAL_BUTTON,
+                hovered_button: HOVERED_BUTTON,
+                pressed_button: PRESSED_BUTTON,
+                disabled_button: DISABLED_BUTTON,
+                disabled_deck: DISABLED_DECK,
+                enabled_deck: ENABLED_DECK,
+                in_progress_deck: IN_PROGRESS_DECK,
+                locked_deck: LOCKED_DECK,
+            },
+            background: Background {
+                image: Handle::default(),
+                color: CLEAR,
+                use_image: false,
+            },
+            text_color: Color::rgb(0.9, 0.9, 0.9),
+            font: Handle::default(),
+            title_size: 100.0,
+            button_font_size: 40.0,
+            text_size: 30.0,
+            button_size: Vec2::new(250.0, 100.0),
+        }
+    }
+}
+
+// "#59BF59" or "59BF59", bad colours get reported and skipped
+fn parse_color(hex: &Option<String>, fallback: Color) -> Color {
+    match hex {
+        Some(hex) => Color::hex(hex.trim_start_matches('#')).unwrap_or_else(|_| {
+            println!("welp, bad color in theme: {}", hex);
+            fallback
+        }),
+        None => fallback,
+    }
+}
+
+impl Theme {
+    pub fn from_json(json: &ThemeJson, asset_server: &AssetServer) -> Self {
+        let default = Theme::default();
+        let colors = &json.colors;
+
+        Theme {
+            name: json.name.clone(),
+            colors: Colors {
+                normal_button: parse_color(&colors.normal_button, default.colors.normal_button),
+                hovered_button: parse_color(&colors.hovered_button, default.colors.hovered_button),
+                pressed_button: parse_color(&colors.pressed_button, default.colors.pressed_button),
+                disabled_button: parse_color(
+                    &colors.disabled_button,
+                    default.colors.disabled_button,
+                ),
+                disabled_deck: parse_color(&colors.disabled_deck, default.colors.disabled_deck),
+                enabled_deck: parse_color(&colors.enabled_deck, default.colors.enabled_deck),
+                in_progress_deck: parse_color(
+                    &colors.in_progress_deck,
+                    default.colors.in_progress_deck,
+                ),
+                locked_deck: parse_color(&colors.locked_deck, default.colors.locked_deck),
+            },
+            background: Background {
+                image: match &json.background_image {
+                    Some(image) => asset_server.load(image.as_str()),
+                    None => Handle::default(),
+                },
+                color: parse_color(&json.background_color, default.background.color),
+                use_image: json.background_image.is_some(),
+            },
+            text_color: parse_color(&json.text_color, default.text_color),
+            font: asset_server.load(json.font.as_deref().unwrap_or("fonts/Roboto.ttf")),
+            title_size: json.title_size.unwrap_or(default.title_size),
+            button_font_size: json.button_font_size.unwrap_or(default.button_font_size),
+            text_size: json.text_size.unwrap_or(default.text_size),
+            button_size: json
+                .button_size
+                .map(Vec2::from)
+                .unwrap_or(default.button_size),
+        }
+    }
+
+    // the theme named in settings.ini, or the built in one if it cant be found
+    pub fn load(settings: &Settings, themes: &ThemesJson, asset_server: &AssetServer) -> Self {
+        let name = settings
+            .settings
+            .get("Theme", "name")
+            .unwrap_or_else(|| "Default".to_owned());
+
+        match themes.find(&name) {
+            Some(json) => Theme::from_json(json, asset_server),
+            None => Theme::from_json(
+                &ThemeJson {
+                    name: "Default".to_owned(),
+                    ..Default::default()
+                },
+                asset_server,
+            ),
+        }
+    }
+}
+
+#[derive(Component)]
+struct ThemeBackground {
+    // the background image, stuck to the camera behind the board
+}
+
+// put the new theme on everything that is already on screen
+fn apply_theme(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+    windows: Res<Windows>,
+    camera_query: Query<Entity, With<MainCamera>>,
+    background_query: Query<Entity, With<ThemeBackground>>,
+    mut button_query: Query<(&mut UiColor, &UiImage), Or<(With<MenuItems>, With<TextInput>)>>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+
+    clear_color.0 = theme.background.color;
+
+    for entity in background_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let (true, Some(window), Some(camera)) = (
+        theme.background.use_image,
+        windows.get_primary(),
+        camera_query.iter().next(),
+    ) {
+        commands.entity(camera).with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(window.width(), window.height())),
+                        ..Default::default()
+                    },
+                    texture: theme.background.image.clone(),
+                    // the camera sits at 999.9, this puts the image just behind the board at 0
+                    transform: Transform::from_xyz(0.0, 0.0, -999.95),
+                    ..Default::default()
+                })
+                .insert(ThemeBackground {});
+        });
+    }
+
+    for (mut color, image) in button_query.iter_mut() {
+        // buttons with a picture on them arent tinted
+        if image.0 == UiImage::default().0 {
+            *color = theme.colors.normal_button.into();
+        }
+    }
+    // the font is handled by UiFont, it knows if a font was picked over the theme's
+}
@@ -0,0 +1,224 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    button_input::{spawn_button, spawn_text},
+    deck::Decks,
+    handle_json::{CurrentRunJson, DeckDataWrapper},
+    localization::Locale,
+    states_and_ui::{GameState, MenuData, MenuItems},
+    theme::Theme,
+};
+
+pub struct TimerPlugin;
+
+impl Plugin for TimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RunClock::default())
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(setup_timers))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(tick_timers)
+                    .with_system(update_timer_text)
+                    .with_system(handle_timer_buttons),
+            );
+    }
+}
+
+// the live clock, kept out of CurrentRunJson so ticking it doesnt mark the run as changed every frame.
+// it gets written back into the run before every change to it and when the run is saved or exported
+#[derive(Default, Debug)]
+pub struct RunClock {
+    pub run: f64,   // seconds on the run clock
+    pub split: f64, // seconds spent on the deck in progress that arent in its split yet
+}
+
+impl RunClock {
+    pub fn write(&mut self, run: &mut CurrentRunJson) {
+        let timers = &mut run.timers;
+
+        timers.run = self.run;
+        if let Some(current) = timers.current {
+            if let Some(split) = timers.splits.get_mut(current) {
+                split.time += self.split;
+            }
+        }
+        self.split = 0.0;
+    }
+
+    // a deck's split including the time that hasnt been written back yet
+    pub fn split_time(&self, run: &CurrentRunJson, deck: usize) -> f64 {
+        let time = run.timers.splits.get(deck).map_or(0.0, |split| split.time);
+        match run.timers.current == Some(deck) {
+            true => time + self.split,
+            false => time,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct TimerString {
+    // track the timer text so it can be updated
+}
+
+fn setup_timers(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    locale: Res<Locale>,
+    current_run_json: Res<CurrentRunJson>,
+    mut clock: ResMut<RunClock>,
+    mut menu_data: ResMut<MenuData>,
+) {
+    // pick up where the save left off
+    *clock = RunClock {
+        run: current_run_json.timers.run,
+        split: 0.0,
+    };
+
+    let text = spawn_text(&mut commands, "", &theme, 40.0, 750.0, 190.0);
+    commands.entity(text).insert(TimerString {});
+    menu_data.button_entity.push(text);
+
+    menu_data.button_entity.push(spawn_button(
+        &mut commands,
+        &theme,
+        &locale.get("timer.splits"),
+        theme.button_font_size,
+        1650.0,
+        780.0,
+        Vec2::new(200.0, 100.0),
+        MenuItems::ExportSplits,
+    ));
+}
+
+// the run clock only runs until every deck is done, splits only run for the deck in progress
+fn tick_timers(
+    time: Res<Time>,
+    current_run_json: Res<CurrentRunJson>,
+    mut clock: ResMut<RunClock>,
+) {
+    if current_run_json.is_finished() {
+        return;
+    }
+
+    let delta = time.delta_seconds_f64();
+
+    clock.run += delta;
+    if current_run_json.timers.current.is_some() {
+        clock.split += delta;
+    }
+}
+
+fn update_timer_text(
+    current_run_json: Res<CurrentRunJson>,
+    clock: Res<RunClock>,
+    deck_data: Res<DeckDataWrapper>,
+    locale: Res<Locale>,
+    mut query: Query<&mut Text, With<TimerString>>,
+) {
+    let mut value = locale.format("timer.run", &[&format_timer(clock.run)]);
+    if let Some(current) = current_run_json.timers.current {
+        value += &format!(
+            "   {}: {}",
+            deck_data.decks[current_run_json.decks[current]].display_name(&locale.language),
+            format_timer(clock.split_time(&current_run_json, current))
+        );
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+fn handle_timer_buttons(
+    mut current_run_json: ResMut<CurrentRunJson>,
+    mut clock: ResMut<RunClock>,
+    decks: Res<Decks>,
+    query: Query<(&Interaction, &MenuItems), (With<Button>, Changed<Interaction>)>,
+) {
+    for (interaction, menu_item) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            if let MenuItems::ExportSplits = menu_item {
+                clock.write(&mut current_run_json);
+                match export_splits(&current_run_json, &decks) {
+                    Ok(path) => println!("Exported splits to {}", path.display()),
+                    Err(err) => println!("Could not export splits: {}", err),
+                }
+            }
+        }
+    }
+}
+
+// h:mm:ss.t
+pub fn format_timer(secs: f64) -> String {
+    let tenths = (secs * 10.0) as u64;
+    format!(
+        "{}:{:02}:{:02}.{}",
+        tenths / 36000,
+        (tenths / 600) % 60,
+        (tenths / 10) % 60,
+        tenths % 10
+    )
+}
+
+// hh:mm:ss.fffffff, the format LiveSplit stores times in
+fn livesplit_time(secs: f64) -> String {
+    let ticks = (secs * 10_000_000.0) as u64;
+    let whole = ticks / 10_000_000;
+    format!(
+        "{:02}:{:02}:{:02}.{:07}",
+        whole / 3600,
+        (whole / 60) % 60,
+        whole % 60,
+        ticks % 10_000_000
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// write the run as a LiveSplit splits file, with the decks in the order they were completed
+pub fn export_splits(run: &CurrentRunJson, decks: &Decks) -> std::io::Result<PathBuf> {
+    let mut order: Vec<usize> = (0..run.decks.len()).collect();
+    order.sort_by(|a, b| {
+        let a = run.timers.splits[*a].finished_at.unwrap_or(f64::MAX);
+        let b = run.timers.splits[*b].finished_at.unwrap_or(f64::MAX);
+        a.partial_cmp(&b).unwrap()
+    });
+
+    let mut segments = String::new();
+    for deck in order {
+        let split = run.timers.splits[deck];
+
+        let split_time = match split.finished_at {
+            Some(finished_at) => format!(
+                "<SplitTime name=\"Personal Best\"><RealTime>{}</RealTime></SplitTime>",
+                livesplit_time(finished_at)
+            ),
+            None => String::from("<SplitTime name=\"Personal Best\" />"),
+        };
+
+        segments += &format!(
+            "    <Segment>\n      <Name>{}</Name>\n      <Icon />\n      <SplitTimes>{}</SplitTimes>\n      <BestSegmentTime><RealTime>{}</RealTime></BestSegmentTime>\n      <SegmentHistory />\n    </Segment>\n",
+            escape_xml(&decks.0[deck].name),
+            split_time,
+            livesplit_time(split.time)
+        );
+    }
+
+    let lss = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Run version=\"1.7.0\">\n  <GameIcon />\n  <GameName>The Pyramid</GameName>\n  <CategoryName>{} Decks</CategoryName>\n  <Offset>00:00:00</Offset>\n  <AttemptCount>1</AttemptCount>\n  <AttemptHistory />\n  <Segments>\n{}  </Segments>\n  <AutoSplitterSettings />\n</Run>\n",
+        run.decks.len(),
+        segments
+    );
+
+    fs::create_dir_all("exports")?;
+    let path = PathBuf::from(format!("exports/splits_{}.lss", run.seed));
+    fs::write(&path, lss)?;
+    Ok(path)
+}